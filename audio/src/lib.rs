@@ -14,8 +14,9 @@ extern crate rodio;
 // STD Dependencies -----------------------------------------------------------
 use std::thread;
 use std::fs::File;
-use std::path::PathBuf;
-use std::io::BufReader;
+use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::io::{self, BufReader, Cursor, Read};
 use std::sync::mpsc::{channel, Sender};
 
 
@@ -23,9 +24,39 @@ use std::sync::mpsc::{channel, Sender};
 use rodio::Source;
 
 
+// Sounds ---------------------------------------------------------------------
+
+/// An encoded sound file kept in memory, clones share the same data.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    data: Arc<Vec<u8>>
+}
+
+impl Sound {
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        Ok(Self {
+            data: Arc::new(data)
+        })
+    }
+
+}
+
+struct SoundData(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for SoundData {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+
 // Public Interface -----------------------------------------------------------
 enum AudioCommand {
     EffectPlay(PathBuf, f32),
+    SoundPlay(Sound, f32),
     MusicStart(PathBuf),
     MusicStop
 }
@@ -51,6 +82,12 @@ impl AudioQueue {
                         let source = rodio::Decoder::new(BufReader::new(file)).unwrap();
                         rodio::play_raw(&endpoint, source.convert_samples().speed(speed));
                     },
+                    AudioCommand::SoundPlay(sound, speed) => {
+                        match rodio::Decoder::new(Cursor::new(SoundData(sound.data))) {
+                            Ok(source) => rodio::play_raw(&endpoint, source.convert_samples().speed(speed)),
+                            Err(err) => println!("[Audio] Failed to decode sound: {:?}", err)
+                        }
+                    },
                     _ => {}
                 }
             }
@@ -68,6 +105,11 @@ impl AudioQueue {
         self.sender.send(Some(AudioCommand::EffectPlay(path, speed.unwrap_or(1.0)))).ok();
     }
 
+    /// Plays a sound which was loaded up front, without reading any files.
+    pub fn play_sound(&mut self, sound: &Sound, speed: Option<f32>) {
+        self.sender.send(Some(AudioCommand::SoundPlay(sound.clone(), speed.unwrap_or(1.0)))).ok();
+    }
+
     pub fn stop(&mut self) {
         if let Some(handle) = self.thread.take() {
             self.sender.send(None).ok();
@@ -21,7 +21,29 @@ use clockwork::{Clockwork, Config, Error, State, Options, Input};
 
 
 // Public Interface -----------------------------------------------------------
-pub fn start<S, O, I, R, C: Fn(Config, u8, Clockwork<S, O, I, R>)>(
+
+/// Game settings passed on the command line.
+#[derive(Debug, Clone)]
+pub struct Arguments {
+    pub min_players: u8,
+    pub ai_players: u8,
+    pub ai_difficulty: u8,
+    pub editor: bool,
+    pub generate_map: bool,
+    /// Victory conditions, see `Victory` in the game.
+    pub annihilation: bool,
+    pub headquarters: bool,
+    pub resource_goal: u32,
+    pub time_limit: u32,
+    /// Name of the map within `assets/maps`, without extension.
+    pub map: String,
+    /// Whether this client also runs the server.
+    pub is_host: bool,
+    /// Directory to load all assets from instead of searching for it.
+    pub assets: Option<String>
+}
+
+pub fn start<S, O, I, R, C: Fn(Config, Arguments, Clockwork<S, O, I, R>)>(
     callback: C
 
 ) -> Result<(), Error> where S: State<O, I, R> + 'static,
@@ -48,21 +70,88 @@ pub fn start<S, O, I, R, C: Fn(Config, u8, Clockwork<S, O, I, R>)>(
             .takes_value(true)
             .help("Required number of clients to start the game (Default is 2).")
 
+        ).arg(clap::Arg::with_name("ai")
+            .long("ai")
+            .takes_value(true)
+            .help("Number of computer players to add to the game (Default is 0).")
+
+        ).arg(clap::Arg::with_name("difficulty")
+            .long("difficulty")
+            .takes_value(true)
+            .possible_values(&["easy", "normal", "hard"])
+            .help("Difficulty of the computer players (Default is normal).")
+
+        ).arg(clap::Arg::with_name("map")
+            .long("map")
+            .takes_value(true)
+            .help("Name of the map to play, clients download it from the host if needed (Default is develop).")
+
+        ).arg(clap::Arg::with_name("generate")
+            .long("generate")
+            .help("Plays on a randomly generated map.")
+
+        ).arg(clap::Arg::with_name("no_annihilation")
+            .long("no_annihilation")
+            .help("Players are no longer defeated once all of their units and buildings are gone.")
+
+        ).arg(clap::Arg::with_name("headquarters")
+            .long("headquarters")
+            .help("Players are defeated once their headquarters have been destroyed.")
+
+        ).arg(clap::Arg::with_name("resources")
+            .long("resources")
+            .takes_value(true)
+            .help("The first player to gather this many resources wins (Default is 0, which disables it).")
+
+        ).arg(clap::Arg::with_name("time_limit")
+            .long("time_limit")
+            .takes_value(true)
+            .help("Ends the game after this many ticks, the players which gathered the most resources win (Default is 0, which disables it).")
+
+        ).arg(clap::Arg::with_name("assets")
+            .long("assets")
+            .takes_value(true)
+            .help("Directory to load assets from (Default is the nearest \"assets\" directory).")
+
+        ).arg(clap::Arg::with_name("editor")
+            .long("editor")
+            .help("Opens the map editor instead of starting a game.")
+
         ).get_matches();
 
+    let ai_difficulty = match args.value_of("difficulty") {
+        Some("easy") => 0,
+        Some("hard") => 2,
+        _ => 1
+    };
+
+    let address = value_t!(args.value_of("address"), Ipv4Addr).ok();
     run(
         value_t!(args.value_of("port"), u16).unwrap_or(28768),
-        value_t!(args.value_of("address"), Ipv4Addr).ok(),
-        value_t!(args.value_of("min_players"), u8).ok().unwrap_or(1),
+        address,
+        Arguments {
+            min_players: value_t!(args.value_of("min_players"), u8).ok().unwrap_or(1),
+            ai_players: value_t!(args.value_of("ai"), u8).ok().unwrap_or(0),
+            ai_difficulty: ai_difficulty,
+            editor: args.is_present("editor"),
+            generate_map: args.is_present("generate"),
+            annihilation: !args.is_present("no_annihilation"),
+            headquarters: args.is_present("headquarters"),
+            resource_goal: value_t!(args.value_of("resources"), u32).ok().unwrap_or(0),
+            time_limit: value_t!(args.value_of("time_limit"), u32).ok().unwrap_or(0),
+            map: args.value_of("map").unwrap_or("develop").to_string(),
+            is_host: address.is_none(),
+            assets: args.value_of("assets").map(|a| a.to_string())
+        },
         callback
     )
 
 }
 
-pub fn run<S, O, I, R, C: Fn(Config, u8, Clockwork<S, O, I, R>)> (
+pub fn run<S, O, I, R, C: Fn(Config, Arguments, Clockwork<S, O, I, R>)> (
     port: u16,
     addr: Option<Ipv4Addr>,
-    min_players: u8,
+    arguments: Arguments,
     callback: C
 
 ) -> Result<(), Error> where S: State<O, I, R> + 'static,
@@ -83,14 +172,14 @@ pub fn run<S, O, I, R, C: Fn(Config, u8, Clockwork<S, O, I, R>)> (
     };
 
     let mut client = Clockwork::<S, O, I, R>::connect(config)?;
-    if addr.is_none() {
+    if addr.is_none() && !arguments.editor {
         println!("[Client] [Network] Starting server on local port {}...", port);
         client = client.with_server()?;
     }
 
     println!("[Client] [Network] Connecting to server at {}:{}...", remote_addr, port);
 
-    callback(config, min_players, client);
+    callback(config, arguments, client);
 
     Ok(())
 
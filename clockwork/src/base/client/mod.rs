@@ -35,6 +35,8 @@ pub enum ClientTick {
     Ready = 2,
     SyncConfirm = 3,
     SendInput = 4,
+    GameOver = 5,
+    Finished = 6,
     Unknown = 255
 }
 
@@ -46,6 +48,8 @@ impl ClientTick {
             2 => ClientTick::Ready,
             3 => ClientTick::SyncConfirm,
             4 => ClientTick::SendInput,
+            5 => ClientTick::GameOver,
+            6 => ClientTick::Finished,
             _ => ClientTick::Unknown
         }
     }
@@ -131,7 +135,11 @@ impl<S, O, I, R> ClockworkClient<S, O, I, R> where S: State<O, I, R>, O: Options
 
     pub fn receive(&mut self, config: &Config, t: u64, refs: &mut R) -> Result<Vec<Event>, Error> {
 
-        if self.local.timed_out(config) {
+        // Once the game is over, hosts are free to go away
+        if self.network_status == NetworkStatus::Finished {
+            Ok(Vec::new())
+
+        } else if self.local.timed_out(config) {
             self.network_status = NetworkStatus::Disconnected;
             Err(Error::RemoteTimeout)
 
@@ -278,18 +286,30 @@ impl<S, O, I, R> ClockworkClient<S, O, I, R> where S: State<O, I, R>, O: Options
                     if options_changed {
                         self.last_options_hash = Some(new_options_hash);
                         self.state.apply_options(self.host_id, &options[..]);
-                        if self.state.is_ready() {
-                            self.local.set_tick(ClientTick::Ready);
-                        }
                         events.push(Event::RemoteOptions);
                     }
 
+                    // States may become ready without any changes to the
+                    // options, e.g. once they finished loading assets
+                    if self.state.is_ready() {
+                        self.local.set_tick(ClientTick::Ready);
+                    }
+
                 },
 
                 ClientEvent::Ready(connections) => {
 
                     if let Some(connections) = connections {
 
+                        for connection in connections {
+                            if !self.connections.contains_key(&connection.id()) {
+                                events.push(Event::RemoteJoined(connection.address(), connection.id()))
+                            }
+                            self.connections.insert(connection.id(), connection.clone());
+                        }
+
+                        // Initialize only after all connections are known so
+                        // the state can set up its players
                         if self.network_status == NetworkStatus::Connecting {
                             let connections = self.connections();
                             self.state.init(
@@ -299,13 +319,6 @@ impl<S, O, I, R> ClockworkClient<S, O, I, R> where S: State<O, I, R>, O: Options
                             );
                         }
 
-                        for connection in connections {
-                            if !self.connections.contains_key(&connection.id()) {
-                                events.push(Event::RemoteJoined(connection.address(), connection.id()))
-                            }
-                            self.connections.insert(connection.id(), connection.clone());
-                        }
-
                     }
 
                     if self.network_status != NetworkStatus::Connected {
@@ -349,12 +362,25 @@ impl<S, O, I, R> ClockworkClient<S, O, I, R> where S: State<O, I, R>, O: Options
                         }
                     });
 
-                    // Tick state
+                    // Tick state, but never past the end of the game so all
+                    // clients stop on the exact same tick
                     let connections = self.connections();
                     for _ in 0..ticks {
+                        if self.state.is_over() {
+                            break;
+                        }
                         self.state.tick(t, self.host_id, &connections[..]);
                     }
 
+                    if self.state.is_over() {
+                        self.local.set_tick(ClientTick::GameOver);
+                    }
+
+                },
+
+                ClientEvent::GameOver => {
+                    self.network_status = NetworkStatus::Finished;
+                    events.push(Event::GameOver);
                 }
 
             }
@@ -386,6 +412,7 @@ enum NetworkStatus {
     Connecting,
     Reconnecting,
     Connected,
-    Disconnected
+    Disconnected,
+    Finished
 }
 
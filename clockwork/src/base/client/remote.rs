@@ -27,7 +27,8 @@ pub enum ClientEvent<O, I> {
     Connected(ConnectionID),
     Options(Vec<RemoteOptions<O>>),
     Ready(Option<Vec<RemoteConnection>>),
-    Inputs(u8, Vec<RemoteInput<I>>)
+    Inputs(u8, Vec<RemoteInput<I>>),
+    GameOver
 }
 
 
@@ -185,7 +186,11 @@ impl<S, O, I, R> ClientRemote<S, O, I, R> where S: State<O, I, R>, O: Options, I
             (ClientTick::Ready, ServerTick::Migrate) |
 
             // Waiting for server to accepts inputs
-            (ClientTick::SyncConfirm, ServerTick::InitializeMigrate) => {
+            (ClientTick::SyncConfirm, ServerTick::InitializeMigrate) |
+
+            // Waiting for all other clients to reach the end of the game
+            (ClientTick::GameOver, ServerTick::AwaitInput) |
+            (ClientTick::Finished, ServerTick::GameOver) => {
             },
 
             (ClientTick::SendOptions, ServerTick::ConfirmOptions) => if packet.len() >= 10 {
@@ -284,6 +289,12 @@ impl<S, O, I, R> ClientRemote<S, O, I, R> where S: State<O, I, R>, O: Options, I
                 }
 
             },
+            (ClientTick::GameOver, ServerTick::GameOver) => {
+                self.tick = ClientTick::Finished;
+                return Some(vec![
+                    ClientEvent::GameOver
+                ]);
+            },
             (_, _) => {
                 println!("Unknown packet {:?}/{:?}", self.tick, ServerTick::from_u8(packet[8]));
                 return None;
@@ -32,6 +32,7 @@ pub enum ServerTick {
     Initialize = 4,
     InitializeMigrate = 5,
     AwaitInput = 6,
+    GameOver = 7,
     Unknown = 255
 }
 
@@ -45,6 +46,7 @@ impl ServerTick {
             4 => ServerTick::Initialize,
             5 => ServerTick::InitializeMigrate,
             6 => ServerTick::AwaitInput,
+            7 => ServerTick::GameOver,
             _ => ServerTick::Unknown
         }
     }
@@ -240,6 +242,14 @@ impl<O, I> ClockworkServer<O, I> where O: Options, I: Input {
 
         }
 
+        // The game only ends once all remotes have reached its end, which
+        // happens within the same input sequence for all of them
+        if self.started && !self.remotes.is_empty() && self.remotes.values().all(|r| r.is_game_over()) {
+            for remote in self.remotes.values_mut() {
+                remote.set_tick(ServerTick::GameOver);
+            }
+        }
+
         // Find lowest ServerTick shared across all remotes
         let lowest_tick = self.remotes.values().min_by_key(|r| r.tick()).map(|r| r.tick());
 
@@ -46,7 +46,10 @@ pub struct ServerRemote<O, I> {
 
     /// Wether this remote was previously disconnected and should be dropped
     /// quickly by any given server.
-    was_disconnected: bool
+    was_disconnected: bool,
+
+    /// Whether the remote has reached the end of the game.
+    game_over: bool
 
 }
 
@@ -67,7 +70,8 @@ impl<O, I> ServerRemote<O, I> where O: Options, I: Input {
             options: None,
             connection: connection.unwrap_or_else(|| RemoteConnection::new(id, address, 0)),
             last_receive_time: Instant::now(),
-            was_disconnected: was_disconnected
+            was_disconnected: was_disconnected,
+            game_over: false
         }
     }
 
@@ -83,6 +87,14 @@ impl<O, I> ServerRemote<O, I> where O: Options, I: Input {
         self.tick
     }
 
+    pub fn set_tick(&mut self, tick: ServerTick) {
+        self.tick = tick;
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
     pub fn input(&self) -> Option<&RemoteInput<I>> {
         self.input.as_ref()
     }
@@ -226,6 +238,14 @@ impl<O, I> ServerRemote<O, I> where O: Options, I: Input {
                     }
                 }
             },
+
+            // Wait for all other clients to reach the end of the game
+            (ServerTick::AwaitInput, ClientTick::GameOver) => {
+                self.game_over = true;
+            },
+            (ServerTick::GameOver, ClientTick::GameOver) |
+            (ServerTick::GameOver, ClientTick::Finished) => {},
+
             (_, _) => {
                 println!("Unknown packet {:?}/{:?}", self.tick, ClientTick::from_u8(packet[8]));
                 return false;
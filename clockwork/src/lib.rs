@@ -47,6 +47,8 @@ pub enum Event {
     RemoteJoined(SocketAddr, ConnectionID),
     RemoteOptions,
     RemoteLeft(SocketAddr, ConnectionID),
+    /// All clients have reached the end of the game on the same tick.
+    GameOver,
     // TODO Waiting For Player Event in case a player is having connection problems
     // TODO Server needs to handle that by sending the event as long as the issues is in progress:
     // Also drop to the low tick rate during that time?
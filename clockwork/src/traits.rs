@@ -32,6 +32,14 @@ pub trait State<O, I, R>: Default {
     fn is_ready(&self) -> bool;
     fn init(&mut self, HostID, &[(ConnectionID, SocketAddr)], &mut R);
     fn tick(&mut self, u64, HostID, &[(ConnectionID, SocketAddr)]);
+
+    /// Once this returns `true` no further ticks are performed and all
+    /// clients agree on ending the game. Games which never end can keep the
+    /// default.
+    fn is_over(&self) -> bool {
+        false
+    }
+
     fn apply_options(&mut self, HostID, &[(ConnectionID, O)]);
     fn apply_input(&mut self, HostID, ConnectionID, I);
 }
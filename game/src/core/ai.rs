@@ -0,0 +1,451 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use rand::{Rng, SeedableRng, XorShiftRng};
+use tiles::GridCell;
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{harvest, Building, BuildingKind, GameInput, GameState, Player, PlayerID};
+use core::{ResourceKind, Target, Unit, UnitKind};
+
+
+// Statics --------------------------------------------------------------------
+const RESOURCE_SEARCH_RADIUS: i32 = 16;
+const BUILD_SEARCH_RADIUS: i32 = 10;
+const DEFEND_RADIUS: i32 = 8;
+const EXPAND_DISTANCE: i32 = 10;
+const BARRACKS_QUEUE: usize = 2;
+
+
+// Difficulty -----------------------------------------------------------------
+#[derive(Debug, Hash, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard
+}
+
+impl Difficulty {
+
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Normal,
+            _ => Difficulty::Hard
+        }
+    }
+
+    /// Number of ticks between two decisions.
+    fn think_ticks(&self) -> u32 {
+        match *self {
+            Difficulty::Easy => 30,
+            Difficulty::Normal => 15,
+            Difficulty::Hard => 8
+        }
+    }
+
+    /// Number of decisions carried out at once.
+    fn decisions(&self) -> usize {
+        match *self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3
+        }
+    }
+
+    fn max_workers(&self) -> usize {
+        match *self {
+            Difficulty::Easy => 6,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 14
+        }
+    }
+
+    fn max_barracks(&self) -> usize {
+        match *self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3
+        }
+    }
+
+    /// Number of idle soldiers required to launch an attack.
+    fn wave_size(&self) -> usize {
+        match *self {
+            Difficulty::Easy => 8,
+            Difficulty::Normal => 6,
+            Difficulty::Hard => 4
+        }
+    }
+
+}
+
+
+// Decisions ------------------------------------------------------------------
+
+/// A group of commands which are carried out together, e.g. all soldiers of
+/// an attack wave.
+#[derive(Debug)]
+struct Decision {
+    utility: u32,
+    commands: Vec<GameInput>
+}
+
+impl Decision {
+    fn new(utility: u32, commands: Vec<GameInput>) -> Self {
+        Self {
+            utility: utility,
+            commands: commands
+        }
+    }
+}
+
+/// Everything a computer player knows about the game when deciding.
+struct Context<'a> {
+    state: &'a GameState,
+    player: &'a Player,
+    base: GridCell,
+    units: Vec<&'a Unit>,
+    buildings: Vec<&'a Building>,
+    worker: Option<UnitKind>,
+    soldier: Option<UnitKind>,
+    headquarters: Option<BuildingKind>,
+    house: Option<BuildingKind>,
+    barracks: Option<BuildingKind>
+}
+
+impl<'a> Context<'a> {
+
+    fn units_of(&self, kind: Option<UnitKind>) -> Vec<&'a Unit> {
+        self.units.iter().filter(|u| Some(u.kind) == kind).cloned().collect()
+    }
+
+    fn buildings_of(&self, kind: Option<BuildingKind>) -> Vec<&'a Building> {
+        self.buildings.iter().filter(|b| Some(b.kind) == kind).cloned().collect()
+    }
+
+    /// Number of units of the given kind, including queued ones.
+    fn planned_units(&self, kind: Option<UnitKind>) -> usize {
+        self.units_of(kind).len() + self.buildings.iter().map(|b| {
+            b.queue().iter().filter(|k| Some(**k) == kind).count()
+
+        }).sum::<usize>()
+    }
+
+    fn can_afford(&self, cost: (u32, u32)) -> bool {
+        self.player.stockpile.get(ResourceKind::Wood) >= cost.0
+            && self.player.stockpile.get(ResourceKind::Stone) >= cost.1
+    }
+
+}
+
+
+// Computer Player ------------------------------------------------------------
+
+/// A computer controlled opponent.
+///
+/// Decisions are part of the simulation and are derived solely from the game
+/// state and a seeded rng, so every client and replay arrives at the same
+/// commands on the same tick.
+#[derive(Debug)]
+pub struct Ai {
+    pub player: PlayerID,
+    difficulty: Difficulty,
+    rng: XorShiftRng,
+    next_think: u32
+}
+
+impl Ai {
+
+    pub fn new(player: PlayerID, difficulty: Difficulty, seed: [u32; 4]) -> Self {
+        Self {
+            player: player,
+            difficulty: difficulty,
+            rng: XorShiftRng::from_seed(seed),
+            // Spread out the decisions of multiple computer players
+            next_think: player.0 as u32
+        }
+    }
+
+    /// Returns the commands of the most useful decisions in case it is time
+    /// to make any.
+    pub fn think(&mut self, state: &GameState) -> Vec<GameInput> {
+
+        if state.ticks() < self.next_think {
+            return Vec::new();
+        }
+
+        self.next_think = state.ticks() + self.difficulty.think_ticks();
+
+        let player = if let Some(player) = state.players().get(self.player.0 as usize) {
+            player
+
+        } else {
+            return Vec::new();
+        };
+
+        if player.defeated {
+            return Vec::new();
+        }
+
+        let id = self.player;
+        let definitions = state.definitions();
+        let buildings: Vec<&Building> = state.buildings().iter().filter(|b| b.owner == id).collect();
+        let headquarters = definitions.building_kind("Headquarters");
+        let base = buildings.iter().find(|b| Some(b.kind) == headquarters).map(|b| {
+            let (origin, (w, h)) = (b.origin(), b.footprint());
+            GridCell(origin.0 + w / 2, origin.1 + h / 2)
+
+        }).unwrap_or(player.start);
+
+        let context = Context {
+            state: state,
+            player: player,
+            base: base,
+            units: state.units().iter().filter(|u| u.owner == id).collect(),
+            buildings: buildings,
+            worker: definitions.unit_kind("Worker"),
+            soldier: definitions.unit_kind("Soldier"),
+            headquarters: headquarters,
+            house: definitions.building_kind("House"),
+            barracks: definitions.building_kind("Barracks")
+        };
+
+        let mut decisions = Vec::new();
+        self.economy(&context, &mut decisions);
+        self.production(&context, &mut decisions);
+        self.construction(&context, &mut decisions);
+        self.military(&context, &mut decisions);
+
+        // Sorting is stable, so equally useful decisions keep their order
+        decisions.sort_by(|a, b| b.utility.cmp(&a.utility));
+        decisions.into_iter().take(self.difficulty.decisions()).flat_map(|d| d.commands).collect()
+
+    }
+
+    // Economy ----------------------------------------------------------------
+    fn economy(&mut self, context: &Context, decisions: &mut Vec<Decision>) {
+
+        let idle: Vec<&Unit> = context.units_of(context.worker).into_iter().filter(|u| {
+            u.is_idle() && u.harvest.is_none() && u.target.is_none()
+
+        }).collect();
+
+        if idle.is_empty() {
+            return;
+        }
+
+        // Keep wood at about twice the amount of stone
+        let stockpile = &context.player.stockpile;
+        let (preferred, other) = if stockpile.get(ResourceKind::Wood) <= stockpile.get(ResourceKind::Stone) * 2 {
+            (ResourceKind::Wood, ResourceKind::Stone)
+
+        } else {
+            (ResourceKind::Stone, ResourceKind::Wood)
+        };
+
+        let resource = find_resource(context, preferred).or_else(|| find_resource(context, other));
+        if let Some(cell) = resource {
+            decisions.push(Decision::new(80, idle.iter().map(|u| {
+                GameInput::MoveUnit(u.id, cell.0 as u8, cell.1 as u8)
+
+            }).collect()));
+        }
+
+    }
+
+    // Production -------------------------------------------------------------
+    fn production(&mut self, context: &Context, decisions: &mut Vec<Decision>) {
+
+        let definitions = context.state.definitions();
+
+        // Workers
+        let workers = context.planned_units(context.worker);
+        if let (Some(kind), Some(definition)) = (context.worker, context.worker.and_then(|k| definitions.unit(k))) {
+            if workers < self.difficulty.max_workers() && context.can_afford(definition.cost) {
+                let producer = context.buildings_of(context.headquarters).into_iter().find(|b| {
+                    b.is_complete() && b.queue().is_empty()
+                });
+                if let Some(b) = producer {
+                    let missing = (self.difficulty.max_workers() - workers) as u32;
+                    decisions.push(Decision::new(60 + missing * 2, vec![GameInput::QueueUnit(b.id, kind)]));
+                }
+            }
+        }
+
+        // Soldiers, more urgently while under attack
+        if let (Some(kind), Some(definition)) = (context.soldier, context.soldier.and_then(|k| definitions.unit(k))) {
+            if context.can_afford(definition.cost) {
+                let producer = context.buildings_of(context.barracks).into_iter().find(|b| {
+                    b.is_complete() && b.queue().len() < BARRACKS_QUEUE
+                });
+                if let Some(b) = producer {
+                    let utility = if threats(context).is_empty() { 50 } else { 85 };
+                    decisions.push(Decision::new(utility, vec![GameInput::QueueUnit(b.id, kind)]));
+                }
+            }
+        }
+
+    }
+
+    // Construction -----------------------------------------------------------
+    fn construction(&mut self, context: &Context, decisions: &mut Vec<Decision>) {
+
+        let workers = context.units_of(context.worker).len();
+        let houses = context.buildings_of(context.house);
+        let barracks = context.buildings_of(context.barracks);
+
+        if houses.is_empty() && workers >= 4 {
+            if let Some(command) = self.place(context, context.house, context.base) {
+                decisions.push(Decision::new(65, vec![command]));
+            }
+
+        } else if barracks.len() < self.difficulty.max_barracks() && houses.iter().any(|b| b.is_complete()) {
+
+            // Only ever construct a single barracks at a time
+            if barracks.iter().all(|b| b.is_complete()) {
+                if let Some(command) = self.place(context, context.barracks, context.base) {
+                    decisions.push(Decision::new(55, vec![command]));
+                }
+            }
+
+        // Expand towards resources which are far away from any headquarters
+        } else if let Some(cell) = find_resource(context, ResourceKind::Wood) {
+            let expansions = context.buildings_of(context.headquarters);
+            let distance = expansions.iter().map(|b| b.distance(cell)).min().unwrap_or(i32::max_value());
+            if distance > EXPAND_DISTANCE && expansions.iter().all(|b| b.is_complete()) {
+                if let Some(command) = self.place(context, context.headquarters, cell) {
+                    decisions.push(Decision::new(45, vec![command]));
+                }
+            }
+        }
+
+    }
+
+    /// Finds a location around `center` where the building can be placed
+    /// while leaving a tile of space to all other buildings.
+    fn place(&mut self, context: &Context, kind: Option<BuildingKind>, center: GridCell) -> Option<GameInput> {
+
+        let kind = kind?;
+        let definition = context.state.definitions().building(kind)?;
+        if !context.can_afford(definition.cost) || !context.state.has_requirements(context.player.id, &definition.requires) {
+            return None;
+        }
+
+        // Start each ring at a random offset to vary the base layout
+        for distance in 2..BUILD_SEARCH_RADIUS + 1 {
+            let cells = ring(center, distance);
+            let offset = self.rng.gen_range(0, cells.len());
+            for i in 0..cells.len() {
+                let origin = cells[(i + offset) % cells.len()];
+                let (w, h) = definition.footprint;
+                let clear = context.state.buildings().iter().all(|b| {
+                    b.distance(origin) > 1
+                        && b.distance(GridCell(origin.0 + w - 1, origin.1 + h - 1)) > 1
+                });
+                if clear && context.state.can_place_building(kind, origin) {
+                    return Some(GameInput::PlaceBuilding(kind, origin.0 as u8, origin.1 as u8));
+                }
+            }
+        }
+
+        None
+
+    }
+
+    // Military ---------------------------------------------------------------
+    fn military(&mut self, context: &Context, decisions: &mut Vec<Decision>) {
+
+        let soldiers = context.units_of(context.soldier);
+
+        // Defend against enemies close to our buildings
+        let threats = threats(context);
+        if let Some(threat) = threats.first() {
+            let commands: Vec<GameInput> = soldiers.iter().filter(|u| u.target.is_none()).map(|u| {
+                GameInput::Attack(u.id, Target::Unit(threat.id))
+
+            }).collect();
+
+            if !commands.is_empty() {
+                decisions.push(Decision::new(90, commands));
+                return;
+            }
+        }
+
+        // Attack once enough soldiers are waiting around
+        let idle: Vec<&Unit> = soldiers.into_iter().filter(|u| u.is_idle() && u.target.is_none()).collect();
+        if idle.len() >= self.difficulty.wave_size() {
+
+            let base = context.base;
+            let id = context.player.id;
+            let state = context.state;
+            let target = state.buildings().iter().filter(|b| b.owner != id).min_by_key(|b| b.distance(base)).map(|b| {
+                Target::Building(b.id)
+
+            }).or_else(|| {
+                state.units().iter().filter(|u| u.owner != id).min_by_key(|u| distance(u.position(), base)).map(|u| {
+                    Target::Unit(u.id)
+                })
+            });
+
+            if let Some(target) = target {
+                decisions.push(Decision::new(40, idle.iter().map(|u| GameInput::Attack(u.id, target)).collect()));
+            }
+
+        }
+
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+
+/// Enemy units close to any of the player's buildings, closest to the base
+/// first.
+fn threats<'a>(context: &Context<'a>) -> Vec<&'a Unit> {
+    let id = context.player.id;
+    let mut threats: Vec<&Unit> = context.state.units().iter().filter(|u| {
+        u.owner != id && context.buildings.iter().any(|b| b.distance(u.position()) <= DEFEND_RADIUS)
+
+    }).collect();
+    threats.sort_by_key(|u| distance(u.position(), context.base));
+    threats
+}
+
+fn find_resource(context: &Context, kind: ResourceKind) -> Option<GridCell> {
+    let terrain = context.state.terrain.as_ref()?;
+    for d in 1..RESOURCE_SEARCH_RADIUS + 1 {
+        for cell in ring(context.base, d) {
+            if harvest::resource_at(terrain, cell) == Some(kind) {
+                return Some(cell);
+            }
+        }
+    }
+    None
+}
+
+/// All cells at exactly `distance` tiles around `center`, in row order.
+fn ring(center: GridCell, distance: i32) -> Vec<GridCell> {
+    let mut cells = Vec::new();
+    for y in center.1 - distance..center.1 + distance + 1 {
+        for x in center.0 - distance..center.0 + distance + 1 {
+            if x == center.0 - distance || x == center.0 + distance || y == center.1 - distance || y == center.1 + distance {
+                cells.push(GridCell(x, y));
+            }
+        }
+    }
+    cells
+}
+
+fn distance(a: GridCell, b: GridCell) -> i32 {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
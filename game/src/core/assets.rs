@@ -0,0 +1,299 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::env;
+use std::fmt;
+use std::fs;
+use std::error::Error;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+
+// External Dependencies ------------------------------------------------------
+use audio::Sound;
+use renderer::{Factory, Texture};
+use tiles::{TileData, TileSet};
+
+
+// Statics --------------------------------------------------------------------
+const ASSET_DIRECTORY: &'static str = "assets";
+const WATCH_INTERVAL: u64 = 500;
+
+
+// Handles --------------------------------------------------------------------
+
+/// Refers to a cached asset, handles stay valid when the asset is reloaded.
+pub struct Handle<T> {
+    index: usize,
+    marker: PhantomData<T>
+}
+
+impl<T> Handle<T> {
+    fn new(index: usize) -> Self {
+        Self {
+            index: index,
+            marker: PhantomData
+        }
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle::new(self.index)
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+/// An asset which was replaced because one of its files changed on disk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reloaded {
+    Texture(Handle<Texture>),
+    TileSet(Handle<TileSet>)
+}
+
+
+// Caches ---------------------------------------------------------------------
+struct Entry<T> {
+    path: PathBuf,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    asset: T
+}
+
+struct Cache<T> {
+    entries: Vec<Entry<T>>
+}
+
+impl<T> Cache<T> {
+
+    fn new() -> Self {
+        Self {
+            entries: Vec::new()
+        }
+    }
+
+    /// Returns the cached asset for `path` or loads it, `files` are the
+    /// files which are watched for changes in addition to `path` itself.
+    fn load<F>(&mut self, path: &Path, files: Vec<PathBuf>, load: F) -> Result<Handle<T>, Box<Error>>
+        where F: FnOnce(&Path) -> Result<T, Box<Error>> {
+
+        let path = fs::canonicalize(path)?;
+        if let Some(index) = self.entries.iter().position(|e| e.path == path) {
+            return Ok(Handle::new(index));
+        }
+
+        let asset = load(&path)?;
+        let files = Some(path.clone()).into_iter().chain(files.into_iter()).map(|file| {
+            let modified = modified(&file);
+            (file, modified)
+
+        }).collect();
+
+        self.entries.push(Entry {
+            path: path,
+            files: files,
+            asset: asset
+        });
+
+        Ok(Handle::new(self.entries.len() - 1))
+
+    }
+
+    fn get(&self, handle: Handle<T>) -> &T {
+        &self.entries[handle.index].asset
+    }
+
+    fn replace(&mut self, handle: Handle<T>, asset: T) {
+        self.entries[handle.index].asset = asset;
+    }
+
+    /// Returns all entries of which any file was modified since the last
+    /// call.
+    fn modified(&mut self) -> Vec<(Handle<T>, PathBuf)> {
+        let mut changed = Vec::new();
+        for (index, entry) in self.entries.iter_mut().enumerate() {
+            let mut is_modified = false;
+            for &mut (ref file, ref mut time) in &mut entry.files {
+                let current = modified(file);
+                if current != *time {
+                    *time = current;
+                    is_modified = true;
+                }
+            }
+            if is_modified {
+                changed.push((Handle::new(index), entry.path.clone()));
+            }
+        }
+        changed
+    }
+
+}
+
+
+// Asset Manager --------------------------------------------------------------
+
+/// Loads and caches all game assets relative to a single root directory.
+///
+/// Assets are loaded once and then referred to by their handle. When
+/// watching is enabled, which is the default for debug builds, textures and
+/// tilesets are reloaded whenever their files change.
+pub struct Assets {
+    root: PathBuf,
+    textures: Cache<Texture>,
+    tilesets: Cache<TileSet>,
+    maps: Cache<TileData>,
+    sounds: Cache<Sound>,
+    watch: bool,
+    checked: Instant
+}
+
+impl Assets {
+
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            textures: Cache::new(),
+            tilesets: Cache::new(),
+            maps: Cache::new(),
+            sounds: Cache::new(),
+            watch: cfg!(debug_assertions),
+            checked: Instant::now()
+        }
+    }
+
+    /// Looks for the asset directory in the working directory, next to the
+    /// executable or in any of their parents.
+    pub fn find_root() -> PathBuf {
+        let cwd = env::current_dir().ok();
+        let exe = env::current_exe().ok();
+        for start in cwd.iter().chain(exe.iter()) {
+            let mut dir = Some(start.as_path());
+            while let Some(d) = dir {
+                let root = d.join(ASSET_DIRECTORY);
+                if root.is_dir() {
+                    return root;
+                }
+                dir = d.parent();
+            }
+        }
+        PathBuf::from(ASSET_DIRECTORY)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves a path relative to the asset root, absolute paths are
+    /// returned as is.
+    pub fn path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.root.join(path)
+    }
+
+    pub fn load_texture<P: AsRef<Path>>(&mut self, factory: &mut Factory, path: P) -> Result<Handle<Texture>, Box<Error>> {
+        let path = self.path(path);
+        self.textures.load(&path, Vec::new(), |path| Texture::new(factory, path))
+    }
+
+    /// Loads a tileset, which is also reloaded when only its image changes.
+    pub fn load_tileset<P: AsRef<Path>>(&mut self, factory: &mut Factory, path: P) -> Result<Handle<TileSet>, Box<Error>> {
+        let path = self.path(path);
+        let image = path.parent().unwrap_or(Path::new(".")).join(TileSet::image_source(&path)?);
+        self.tilesets.load(&path, vec![image], |path| TileSet::new(factory, path))
+    }
+
+    pub fn load_map<P: AsRef<Path>>(&mut self, path: P) -> Result<Handle<TileData>, Box<Error>> {
+        let path = self.path(path);
+        self.maps.load(&path, Vec::new(), |path| Ok(TileData::new(path)?))
+    }
+
+    pub fn load_sound<P: AsRef<Path>>(&mut self, path: P) -> Result<Handle<Sound>, Box<Error>> {
+        let path = self.path(path);
+        self.sounds.load(&path, Vec::new(), |path| Ok(Sound::load(path)?))
+    }
+
+    pub fn texture(&self, handle: Handle<Texture>) -> &Texture {
+        self.textures.get(handle)
+    }
+
+    pub fn tileset(&self, handle: Handle<TileSet>) -> &TileSet {
+        self.tilesets.get(handle)
+    }
+
+    pub fn map(&self, handle: Handle<TileData>) -> &TileData {
+        self.maps.get(handle)
+    }
+
+    pub fn sound(&self, handle: Handle<Sound>) -> &Sound {
+        self.sounds.get(handle)
+    }
+
+    /// Reloads all textures and tilesets whose files changed since the last
+    /// check.
+    ///
+    /// Assets which fail to load keep their previous version, so a
+    /// half-written file never breaks a running game.
+    pub fn reload(&mut self, factory: &mut Factory) -> Vec<Reloaded> {
+
+        let mut reloaded = Vec::new();
+        if !self.watch || self.checked.elapsed() < Duration::from_millis(WATCH_INTERVAL) {
+            return reloaded;
+        }
+
+        self.checked = Instant::now();
+
+        for (handle, path) in self.textures.modified() {
+            match Texture::new(factory, &path) {
+                Ok(texture) => {
+                    println!("[Assets] Reloaded texture {:?}", path);
+                    self.textures.replace(handle, texture);
+                    reloaded.push(Reloaded::Texture(handle));
+                },
+                Err(err) => println!("[Assets] Failed to reload texture {:?}: {}", path, err)
+            }
+        }
+
+        for (handle, path) in self.tilesets.modified() {
+            match TileSet::new(factory, &path) {
+                Ok(tileset) => {
+                    println!("[Assets] Reloaded tileset {:?}", path);
+                    self.tilesets.replace(handle, tileset);
+                    reloaded.push(Reloaded::TileSet(handle));
+                },
+                Err(err) => println!("[Assets] Failed to reload tileset {:?}: {}", path, err)
+            }
+        }
+
+        reloaded
+
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
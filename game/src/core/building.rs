@@ -0,0 +1,188 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::collections::VecDeque;
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, TerrainGrid, TileType};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{BuildingDefinition, BuildingKind, Occupancy, PlayerID, UnitKind};
+
+
+// Statics --------------------------------------------------------------------
+const PRODUCTION_QUEUE_MAX: usize = 5;
+
+
+// Building Abstraction -------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct BuildingID(pub u16);
+
+#[derive(Debug)]
+pub struct Building {
+    pub id: BuildingID,
+    pub kind: BuildingKind,
+    pub owner: PlayerID,
+    pub rally: Option<GridCell>,
+    pub health: u16,
+    origin: GridCell,
+    footprint: (i32, i32),
+    build_ticks: u16,
+    progress: u16,
+    queue: VecDeque<UnitKind>,
+    production: u16
+}
+
+impl Building {
+
+    pub fn new(id: BuildingID, definition: &BuildingDefinition, kind: BuildingKind, owner: PlayerID, origin: GridCell) -> Self {
+        Self {
+            id: id,
+            kind: kind,
+            owner: owner,
+            rally: None,
+            health: definition.health,
+            origin: origin,
+            footprint: definition.footprint,
+            build_ticks: definition.build_ticks,
+            progress: 0,
+            queue: VecDeque::new(),
+            production: 0
+        }
+    }
+
+    /// Creates an already constructed building, e.g. for starting bases.
+    pub fn completed(id: BuildingID, definition: &BuildingDefinition, kind: BuildingKind, owner: PlayerID, origin: GridCell) -> Self {
+        let mut building = Self::new(id, definition, kind, owner, origin);
+        building.progress = building.build_ticks;
+        building
+    }
+
+    pub fn origin(&self) -> GridCell {
+        self.origin
+    }
+
+    pub fn footprint(&self) -> (i32, i32) {
+        self.footprint
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress >= self.build_ticks
+    }
+
+    pub fn construct(&mut self) {
+        if !self.is_complete() {
+            self.progress += 1;
+        }
+    }
+
+    pub fn cells(&self) -> Vec<GridCell> {
+        let (w, h) = self.footprint;
+        let mut cells = Vec::with_capacity((w * h) as usize);
+        for y in self.origin.1..self.origin.1 + h {
+            for x in self.origin.0..self.origin.0 + w {
+                cells.push(GridCell(x, y));
+            }
+        }
+        cells
+    }
+
+    pub fn contains(&self, cell: GridCell) -> bool {
+        let (w, h) = self.footprint;
+        cell.0 >= self.origin.0 && cell.0 < self.origin.0 + w
+            && cell.1 >= self.origin.1 && cell.1 < self.origin.1 + h
+    }
+
+    /// Returns the number of tiles between `cell` and the closest edge of the
+    /// building's footprint.
+    pub fn distance(&self, cell: GridCell) -> i32 {
+        let (w, h) = self.footprint;
+        let dx = (self.origin.0 - cell.0).max(cell.0 - (self.origin.0 + w - 1)).max(0);
+        let dy = (self.origin.1 - cell.1).max(cell.1 - (self.origin.1 + h - 1)).max(0);
+        dx.max(dy)
+    }
+
+    // Production -------------------------------------------------------------
+    pub fn queue(&self) -> &VecDeque<UnitKind> {
+        &self.queue
+    }
+
+    pub fn queue_unit(&mut self, kind: UnitKind) -> bool {
+        if self.is_complete() && self.queue.len() < PRODUCTION_QUEUE_MAX {
+            self.queue.push_back(kind);
+            true
+
+        } else {
+            false
+        }
+    }
+
+    /// Removes the most recently queued unit.
+    pub fn cancel_unit(&mut self) -> Option<UnitKind> {
+        let kind = self.queue.pop_back();
+        if self.queue.is_empty() {
+            self.production = 0;
+        }
+        kind
+    }
+
+    /// Advances the unit at the front of the queue and returns it once it
+    /// is ready to be spawned.
+    ///
+    /// The unit stays at the front of the queue until `finish_production` is
+    /// called, so a blocked exit simply delays it.
+    pub fn advance_production(&mut self, build_ticks: u16) -> Option<UnitKind> {
+        if let Some(kind) = self.queue.front().cloned() {
+            if self.production < build_ticks {
+                self.production += 1;
+            }
+            if self.production >= build_ticks {
+                Some(kind)
+
+            } else {
+                None
+            }
+
+        } else {
+            None
+        }
+    }
+
+    pub fn finish_production(&mut self) {
+        self.queue.pop_front();
+        self.production = 0;
+    }
+
+}
+
+
+// Placement ------------------------------------------------------------------
+
+/// Checks whether a building can be placed with its top left corner at
+/// `origin`.
+///
+/// All tiles of the footprint must be plain ground which is neither covered
+/// by another building nor held by any unit.
+pub fn can_place(
+    definition: &BuildingDefinition,
+    origin: GridCell,
+    terrain: &TerrainGrid,
+    occupancy: &Occupancy,
+    buildings: &[Building]
+
+) -> bool {
+    definition.cells(origin).into_iter().all(|c| {
+        terrain.get_tile_index(c.0, c.1).map(|i| terrain.tileset().typ(i) == TileType::Ground).unwrap_or(false)
+            && occupancy.is_free(c)
+            && !buildings.iter().any(|b| b.contains(c))
+    })
+}
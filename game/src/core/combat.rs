@@ -0,0 +1,232 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use rand::{Rng, XorShiftRng};
+use tiles::{GridCell, NavData};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{Building, BuildingID, Definitions, PlayerID, Unit, UnitID};
+
+
+// Combat Targets -------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    Unit(UnitID),
+    Building(BuildingID)
+}
+
+/// Position and owner of a target at the start of the current tick.
+#[derive(Debug, Copy, Clone)]
+struct Candidate {
+    target: Target,
+    owner: PlayerID,
+    cell: GridCell,
+    building: Option<usize>
+}
+
+
+// Projectiles ----------------------------------------------------------------
+
+/// A ranged attack on its way to the target.
+///
+/// Whether the projectile hits is decided when it is fired, its damage is
+/// only applied on arrival in case the target is still alive by then.
+#[derive(Debug)]
+pub struct Projectile {
+    pub owner: PlayerID,
+    target: Target,
+    from: GridCell,
+    to: GridCell,
+    damage: u16,
+    ticks: u16,
+    remaining: u16
+}
+
+impl Projectile {
+
+    pub fn world_position(&self, tile_size: f32) -> (f32, f32) {
+        let p = (self.ticks - self.remaining) as f32 / self.ticks as f32;
+        let (x, y) = (self.from.0 as f32 * tile_size, self.from.1 as f32 * tile_size);
+        let (tx, ty) = (self.to.0 as f32 * tile_size, self.to.1 as f32 * tile_size);
+        (x + (tx - x) * p, y + (ty - y) * p)
+    }
+
+}
+
+
+// Combat Logic ---------------------------------------------------------------
+
+/// Orders the unit to attack the target until either of them is dead.
+pub fn attack(unit: &mut Unit, target: Target) {
+    unit.harvest = None;
+    unit.target = Some(target);
+    unit.pursue = true;
+}
+
+/// Advances all attacks and projectiles by one tick.
+///
+/// Units which are idle automatically engage the closest enemy within their
+/// range but, unlike ordered attacks, do not pursue it.
+///
+/// Units and buildings whose health dropped to zero are left in place for
+/// the caller to remove.
+pub fn tick(
+    units: &mut [Unit],
+    buildings: &mut [Building],
+    projectiles: &mut Vec<Projectile>,
+    definitions: &Definitions,
+    nav: &NavData,
+    rng: &mut XorShiftRng
+) {
+
+    let mut hits = Vec::new();
+    {
+
+        let mut candidates: Vec<Candidate> = units.iter().filter(|u| u.health > 0).map(|u| Candidate {
+            target: Target::Unit(u.id),
+            owner: u.owner,
+            cell: u.position(),
+            building: None
+
+        }).collect();
+
+        candidates.extend(buildings.iter().enumerate().filter(|&(_, b)| b.health > 0).map(|(i, b)| Candidate {
+            target: Target::Building(b.id),
+            owner: b.owner,
+            cell: b.origin(),
+            building: Some(i)
+        }));
+
+        let distance = |from: GridCell, c: &Candidate| {
+            if let Some(i) = c.building {
+                buildings[i].distance(from)
+
+            } else {
+                (from.0 - c.cell.0).abs().max((from.1 - c.cell.1).abs())
+            }
+        };
+
+        for unit in units.iter_mut() {
+
+            let attack = if let Some(attack) = definitions.unit(unit.kind).and_then(|d| d.attack) {
+                attack
+
+            } else {
+                continue;
+            };
+
+            unit.cooldown = unit.cooldown.saturating_sub(1);
+
+            let p = unit.position();
+            let owner = unit.owner;
+
+            // Drop targets which died in the meantime
+            let mut current = unit.target.and_then(|t| candidates.iter().find(|c| c.target == t).cloned());
+            if current.is_none() {
+                unit.target = None;
+                unit.pursue = false;
+            }
+
+            // Idle units engage enemies which come into range, preferring units
+            // over buildings
+            if current.is_none() && unit.is_idle() && unit.harvest.is_none() {
+                current = candidates.iter().filter(|c| {
+                    c.owner != owner && distance(p, c) <= attack.range
+
+                }).min_by_key(|c| (c.building.is_some(), distance(p, c))).cloned();
+
+                unit.target = current.map(|c| c.target);
+            }
+
+            let target = if let Some(target) = current {
+                target
+
+            } else {
+                continue;
+            };
+
+            let d = distance(p, &target);
+            if d <= attack.range {
+
+                // Halt and fire once the current step has been completed
+                if !unit.is_idle() {
+                    unit.stop();
+                }
+
+                if !unit.is_moving() && unit.cooldown == 0 {
+                    unit.cooldown = attack.cooldown;
+
+                    // DE-SYNC: Always roll both values so the rng advances
+                    // identically regardless of the outcome
+                    let roll: u8 = rng.gen_range(0, 100);
+                    let variance: u16 = rng.gen_range(0, attack.variance + 1);
+                    let damage = if roll < attack.accuracy {
+                        attack.damage + variance
+
+                    } else {
+                        0
+                    };
+
+                    if attack.is_ranged() {
+                        let ticks = attack.projectile_ticks * d.max(1) as u16;
+                        projectiles.push(Projectile {
+                            owner: owner,
+                            target: target.target,
+                            from: p,
+                            to: target.cell,
+                            damage: damage,
+                            ticks: ticks,
+                            remaining: ticks
+                        });
+
+                    } else if damage > 0 {
+                        hits.push((target.target, damage));
+                    }
+                }
+
+            } else if unit.pursue {
+                if unit.goal() != Some(target.cell) {
+                    unit.move_to(nav, target.cell);
+                }
+
+            // Out of range targets which were picked up automatically are dropped
+            } else {
+                unit.target = None;
+            }
+
+        }
+
+    }
+
+    // Projectiles
+    for projectile in projectiles.iter_mut() {
+        projectile.remaining = projectile.remaining.saturating_sub(1);
+        if projectile.remaining == 0 && projectile.damage > 0 {
+            hits.push((projectile.target, projectile.damage));
+        }
+    }
+    projectiles.retain(|p| p.remaining > 0);
+
+    // Armor reduces every hit, but never below a single point of damage
+    for (target, damage) in hits {
+        match target {
+            Target::Unit(id) => if let Some(u) = units.iter_mut().find(|u| u.id == id) {
+                let armor = definitions.unit(u.kind).map(|d| d.armor).unwrap_or(0);
+                u.health = u.health.saturating_sub(damage.saturating_sub(armor).max(1));
+            },
+            Target::Building(id) => if let Some(b) = buildings.iter_mut().find(|b| b.id == id) {
+                let armor = definitions.building(b.kind).map(|d| d.armor).unwrap_or(0);
+                b.health = b.health.saturating_sub(damage.saturating_sub(armor).max(1));
+            }
+        }
+    }
+
+}
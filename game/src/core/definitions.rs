@@ -0,0 +1,225 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::fs::File;
+use std::path::Path;
+use std::error::Error;
+use std::io::Read;
+
+
+// External Dependencies ------------------------------------------------------
+use toml;
+use tiles::GridCell;
+
+
+// Kinds ----------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct BuildingKind(pub u8);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct UnitKind(pub u8);
+
+
+// Definitions ----------------------------------------------------------------
+#[derive(Debug)]
+pub struct BuildingDefinition {
+    pub name: String,
+    pub footprint: (i32, i32),
+    pub cost: (u32, u32),
+    pub build_ticks: u16,
+    pub health: u16,
+    pub armor: u16,
+    pub dropoff: bool,
+    pub requires: Vec<BuildingKind>,
+    pub produces: Vec<UnitKind>
+}
+
+impl BuildingDefinition {
+
+    /// All tiles covered by the building when placed at `origin`.
+    pub fn cells(&self, origin: GridCell) -> Vec<GridCell> {
+        let (w, h) = self.footprint;
+        let mut cells = Vec::with_capacity((w * h) as usize);
+        for y in origin.1..origin.1 + h {
+            for x in origin.0..origin.0 + w {
+                cells.push(GridCell(x, y));
+            }
+        }
+        cells
+    }
+
+}
+
+#[derive(Debug)]
+pub struct UnitDefinition {
+    pub name: String,
+    pub cost: (u32, u32),
+    pub build_ticks: u16,
+    pub sight: u8,
+    pub health: u16,
+    pub armor: u16,
+    pub harvester: bool,
+    pub attack: Option<AttackDefinition>,
+    pub requires: Vec<BuildingKind>
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub struct AttackDefinition {
+    pub damage: u16,
+    pub range: i32,
+    pub cooldown: u16,
+    /// Chance to hit in percent.
+    pub accuracy: u8,
+    /// Maximum additional random damage.
+    #[serde(default)]
+    pub variance: u16,
+    /// Travel time per tile, melee attacks have none.
+    #[serde(default)]
+    pub projectile_ticks: u16
+}
+
+impl AttackDefinition {
+    pub fn is_ranged(&self) -> bool {
+        self.projectile_ticks > 0
+    }
+}
+
+
+/// The building and unit table of the game.
+///
+/// Kinds are indices into the table, so all clients must load identical
+/// definitions in order to stay in sync.
+#[derive(Debug, Default)]
+pub struct Definitions {
+    buildings: Vec<BuildingDefinition>,
+    units: Vec<UnitDefinition>
+}
+
+impl Definitions {
+
+    pub fn load(path: &Path) -> Result<Self, Box<Error>> {
+
+        let mut text = String::new();
+        File::open(path)?.read_to_string(&mut text)?;
+
+        let table: Table = toml::from_str(&text)?;
+
+        let building_kind = |name: &String| {
+            table.buildings.iter().position(|b| b.name == *name).map(|i| BuildingKind(i as u8)).ok_or_else(|| {
+                format!("Unknown building \"{}\"", name)
+            })
+        };
+
+        let unit_kind = |name: &String| {
+            table.units.iter().position(|u| u.name == *name).map(|i| UnitKind(i as u8)).ok_or_else(|| {
+                format!("Unknown unit \"{}\"", name)
+            })
+        };
+
+        let mut buildings = Vec::with_capacity(table.buildings.len());
+        for b in &table.buildings {
+            buildings.push(BuildingDefinition {
+                name: b.name.clone(),
+                footprint: (b.width, b.height),
+                cost: (b.wood, b.stone),
+                build_ticks: b.build_ticks,
+                health: b.health,
+                armor: b.armor,
+                dropoff: b.dropoff,
+                requires: b.requires.iter().map(&building_kind).collect::<Result<_, _>>()?,
+                produces: b.produces.iter().map(&unit_kind).collect::<Result<_, _>>()?
+            });
+        }
+
+        let mut units = Vec::with_capacity(table.units.len());
+        for u in &table.units {
+            units.push(UnitDefinition {
+                name: u.name.clone(),
+                cost: (u.wood, u.stone),
+                build_ticks: u.build_ticks,
+                sight: u.sight,
+                health: u.health,
+                armor: u.armor,
+                harvester: u.harvester,
+                attack: u.attack,
+                requires: u.requires.iter().map(&building_kind).collect::<Result<_, _>>()?
+            });
+        }
+
+        Ok(Self {
+            buildings: buildings,
+            units: units
+        })
+
+    }
+
+    pub fn building(&self, kind: BuildingKind) -> Option<&BuildingDefinition> {
+        self.buildings.get(kind.0 as usize)
+    }
+
+    pub fn unit(&self, kind: UnitKind) -> Option<&UnitDefinition> {
+        self.units.get(kind.0 as usize)
+    }
+
+    pub fn building_kind(&self, name: &str) -> Option<BuildingKind> {
+        self.buildings.iter().position(|b| b.name == name).map(|i| BuildingKind(i as u8))
+    }
+
+    pub fn unit_kind(&self, name: &str) -> Option<UnitKind> {
+        self.units.iter().position(|u| u.name == name).map(|i| UnitKind(i as u8))
+    }
+
+}
+
+
+// Definition File ------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+struct Table {
+    #[serde(default)]
+    buildings: Vec<BuildingEntry>,
+    #[serde(default)]
+    units: Vec<UnitEntry>
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildingEntry {
+    name: String,
+    width: i32,
+    height: i32,
+    wood: u32,
+    stone: u32,
+    build_ticks: u16,
+    health: u16,
+    #[serde(default)]
+    armor: u16,
+    #[serde(default)]
+    dropoff: bool,
+    #[serde(default)]
+    requires: Vec<String>,
+    #[serde(default)]
+    produces: Vec<String>
+}
+
+#[derive(Debug, Deserialize)]
+struct UnitEntry {
+    name: String,
+    wood: u32,
+    stone: u32,
+    build_ticks: u16,
+    sight: u8,
+    health: u16,
+    #[serde(default)]
+    armor: u16,
+    #[serde(default)]
+    harvester: bool,
+    attack: Option<AttackDefinition>,
+    #[serde(default)]
+    requires: Vec<String>
+}
@@ -0,0 +1,206 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::iter;
+use std::mem;
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, OverlayShade, TileData, TileSet, TileSource};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{PlayerID, Unit};
+
+
+// Visibility Grids -----------------------------------------------------------
+#[derive(Debug)]
+struct Visibility {
+    explored: Vec<bool>,
+    visible: Vec<bool>,
+    generation: u32
+}
+
+
+// Fog of War -----------------------------------------------------------------
+
+/// Tracks the explored and currently visible tiles of every player.
+///
+/// Fog is purely informational, the simulation itself always operates on the
+/// full map so that all clients stay in lockstep.
+#[derive(Debug)]
+pub struct Fog {
+    width: u32,
+    height: u32,
+    opaque: Vec<bool>,
+    players: Vec<Visibility>
+}
+
+impl Fog {
+
+    pub fn new(width: u32, height: u32, players: usize) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width: width,
+            height: height,
+            opaque: iter::repeat(false).take(size).collect(),
+            players: (0..players).map(|_| Visibility {
+                explored: iter::repeat(false).take(size).collect(),
+                visible: iter::repeat(false).take(size).collect(),
+                generation: 0
+
+            }).collect()
+        }
+    }
+
+    /// Re-computes which tiles block the line of sight, e.g. after terrain has
+    /// been consumed.
+    pub fn update_terrain(&mut self, data: &TileData, tileset: &TileSet) {
+        for (i, opaque) in self.opaque.iter_mut().enumerate() {
+            let tile = data.index(i);
+            *opaque = tileset.get_tile_terrain(tile).map(|t| t.is_consumable_tile(tile)).unwrap_or(false);
+        }
+    }
+
+    pub fn tick(&mut self, units: &[Unit]) {
+
+        let size = (self.width * self.height) as usize;
+        let previous: Vec<Vec<bool>> = self.players.iter_mut().map(|player| {
+            mem::replace(&mut player.visible, iter::repeat(false).take(size).collect())
+
+        }).collect();
+
+        for unit in units {
+            let p = unit.position();
+            let r = unit.sight as i32;
+            for y in p.1 - r..p.1 + r + 1 {
+                for x in p.0 - r..p.0 + r + 1 {
+                    let (dx, dy) = (x - p.0, y - p.1);
+                    if dx * dx + dy * dy <= r * r {
+                        if let Some(i) = self.offset(GridCell(x, y)) {
+                            if self.has_line_of_sight(p, GridCell(x, y)) {
+                                if let Some(player) = self.players.get_mut(unit.owner.0 as usize) {
+                                    player.visible[i] = true;
+                                    player.explored[i] = true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Tiles only become explored while they are visible, so comparing
+        // the visible tiles is enough to detect all changes
+        for (player, previous) in self.players.iter_mut().zip(previous.into_iter()) {
+            if player.visible != previous {
+                player.generation = player.generation.wrapping_add(1);
+            }
+        }
+
+    }
+
+    /// Changes whenever the shades of the player change, so they only need
+    /// to be rebuilt when this differs from the last call.
+    pub fn generation(&self, player: PlayerID) -> Option<u32> {
+        self.players.get(player.0 as usize).map(|p| p.generation)
+    }
+
+    pub fn is_visible(&self, player: PlayerID, cell: GridCell) -> bool {
+        match (self.players.get(player.0 as usize), self.offset(cell)) {
+            (Some(p), Some(i)) => p.visible[i],
+            _ => false
+        }
+    }
+
+    pub fn is_explored(&self, player: PlayerID, cell: GridCell) -> bool {
+        match (self.players.get(player.0 as usize), self.offset(cell)) {
+            (Some(p), Some(i)) => p.explored[i],
+            _ => false
+        }
+    }
+
+    /// Returns the overlay shades of all map tiles for the given player.
+    pub fn shades(&self, player: PlayerID) -> Vec<OverlayShade> {
+        if let Some(p) = self.players.get(player.0 as usize) {
+            p.visible.iter().zip(p.explored.iter()).map(|(&visible, &explored)| {
+                if visible {
+                    OverlayShade::Clear
+
+                } else if explored {
+                    OverlayShade::Dim
+
+                } else {
+                    OverlayShade::Dark
+                }
+
+            }).collect()
+
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Walks a line between both cells, any opaque tile in between blocks the
+    /// view. The target tile itself is always visible so that the edges of
+    /// forests and rocks can be seen.
+    fn has_line_of_sight(&self, from: GridCell, to: GridCell) -> bool {
+
+        let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+        let (sx, sy) = (if from.0 < to.0 { 1 } else { -1 }, if from.1 < to.1 { 1 } else { -1 });
+        let (mut x, mut y) = (from.0, from.1);
+        let mut err = dx + dy;
+
+        loop {
+
+            if x == to.0 && y == to.1 {
+                return true;
+            }
+
+            if (x != from.0 || y != from.1) && self.is_opaque(x, y) {
+                return false;
+            }
+
+            let e2 = err * 2;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+
+        }
+
+    }
+
+    fn is_opaque(&self, x: i32, y: i32) -> bool {
+        self.offset(GridCell(x, y)).map(|i| self.opaque[i]).unwrap_or(true)
+    }
+
+    fn offset(&self, cell: GridCell) -> Option<usize> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        if cell.0 >= 0 && cell.0 < w && cell.1 >= 0 && cell.1 < h {
+            Some((cell.1 * w + cell.0) as usize)
+
+        } else {
+            None
+        }
+    }
+
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self::new(0, 0, 0)
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::collections::BTreeMap;
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, NavData, TerrainGrid};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{Building, BuildingID, Definitions, Player, ResourceKind, ResourceNode, Unit};
+
+
+// Statics --------------------------------------------------------------------
+const GATHER_TICKS: u8 = 10;
+const GATHER_AMOUNT: u16 = 2;
+const BUCKET_CAPACITY: u16 = 10;
+const SEARCH_RADIUS: i32 = 5;
+const HEADQUARTERS_RADIUS: i32 = 2;
+
+
+// Harvesting State -----------------------------------------------------------
+#[derive(Debug, Copy, Clone)]
+enum Phase {
+    Approach(GridCell),
+    Gather(GridCell, u8),
+    Return(BuildingID)
+}
+
+/// The harvesting state machine of a single unit.
+///
+/// `source` is the last location a resource was gathered at, new resource
+/// tiles are searched around it once the current one runs out.
+#[derive(Debug, Copy, Clone)]
+pub struct Harvest {
+    kind: ResourceKind,
+    source: GridCell,
+    carried: u16,
+    phase: Phase
+}
+
+impl Harvest {
+
+    pub fn new(kind: ResourceKind, source: GridCell) -> Self {
+        Self {
+            kind: kind,
+            source: source,
+            carried: 0,
+            phase: Phase::Approach(source)
+        }
+    }
+
+    pub fn kind(&self) -> ResourceKind {
+        self.kind
+    }
+
+    pub fn carried(&self) -> u16 {
+        self.carried
+    }
+
+}
+
+
+// Harvesting Logic -----------------------------------------------------------
+
+/// Starts harvesting the resource at `cell` in case there is one.
+pub fn start(unit: &mut Unit, terrain: &TerrainGrid, nav: &NavData, cell: GridCell) -> bool {
+    if let Some(kind) = resource_at(terrain, cell) {
+
+        // Keep what has already been gathered from the same resource type
+        let carried = match unit.harvest {
+            Some(h) if h.kind == kind => h.carried,
+            _ => 0
+        };
+
+        let mut harvest = Harvest::new(kind, cell);
+        harvest.carried = carried;
+        unit.move_to(nav, cell);
+        unit.harvest = Some(harvest);
+        true
+
+    } else {
+        false
+    }
+}
+
+/// Advances the harvesting of all units by one tick.
+///
+/// Returns the tiles whose resource nodes were exhausted during this tick so
+/// the caller can reflow the terrain.
+pub fn tick(
+    units: &mut [Unit],
+    resources: &mut BTreeMap<GridCell, ResourceNode>,
+    players: &mut [Player],
+    buildings: &[Building],
+    definitions: &Definitions,
+    terrain: &TerrainGrid,
+    nav: &NavData
+
+) -> Vec<GridCell> {
+
+    // Only completed buildings which accept resources
+    let dropoffs: Vec<&Building> = buildings.iter().filter(|b| {
+        b.is_complete() && definitions.building(b.kind).map(|d| d.dropoff).unwrap_or(false)
+
+    }).collect();
+
+    let mut exhausted = Vec::new();
+    for unit in units.iter_mut() {
+        if let Some(harvest) = unit.harvest.take() {
+            unit.harvest = tick_unit(unit, harvest, resources, players, &dropoffs, terrain, nav, &mut exhausted);
+        }
+    }
+    exhausted
+
+}
+
+pub fn resource_at(terrain: &TerrainGrid, cell: GridCell) -> Option<ResourceKind> {
+    if let Some(index) = terrain.get_tile_index(cell.0, cell.1) {
+        terrain.tileset().get_tile_terrain(index).and_then(|t| {
+            if t.is_consumable_tile(index) {
+                ResourceKind::from_terrain(&t.name)
+
+            } else {
+                None
+            }
+        })
+
+    } else {
+        None
+    }
+}
+
+fn tick_unit(
+    unit: &mut Unit,
+    mut harvest: Harvest,
+    resources: &mut BTreeMap<GridCell, ResourceNode>,
+    players: &mut [Player],
+    dropoffs: &[&Building],
+    terrain: &TerrainGrid,
+    nav: &NavData,
+    exhausted: &mut Vec<GridCell>
+
+) -> Option<Harvest> {
+
+    let kind = harvest.kind;
+    let is_resource = |cell: GridCell, exhausted: &[GridCell]| {
+        resource_at(terrain, cell) == Some(kind) && !exhausted.contains(&cell)
+    };
+
+    match harvest.phase {
+
+        Phase::Approach(cell) => if unit.is_idle() {
+
+            // Prefer the targeted tile but accept any other one we ended up
+            // next to, e.g. when the target lies within a larger forest
+            let p = unit.position();
+            let next = if is_adjacent(p, cell) && is_resource(cell, exhausted) {
+                Some(cell)
+
+            } else {
+                find_resource(p, 1, |c| is_resource(c, exhausted))
+            };
+
+            if let Some(cell) = next {
+                harvest.source = cell;
+                harvest.phase = Phase::Gather(cell, 0);
+
+            } else if harvest.carried > 0 {
+                harvest.phase = return_to_headquarters(unit, dropoffs, nav)?;
+
+            // No reachable resource, give up
+            } else {
+                return None;
+            }
+
+        },
+
+        Phase::Gather(cell, ticks) => if !is_resource(cell, exhausted) {
+            harvest.phase = next_resource(unit, &harvest, dropoffs, nav, |c| is_resource(c, exhausted))?;
+
+        } else if ticks + 1 < GATHER_TICKS {
+            harvest.phase = Phase::Gather(cell, ticks + 1);
+
+        } else {
+
+            let taken = {
+                let node = resources.entry(cell).or_insert_with(|| ResourceNode::new(kind));
+                node.take(GATHER_AMOUNT.min(BUCKET_CAPACITY - harvest.carried))
+            };
+            harvest.carried += taken;
+
+            if resources.get(&cell).map(|n| n.is_exhausted()).unwrap_or(false) {
+                resources.remove(&cell);
+                exhausted.push(cell);
+            }
+
+            if harvest.carried >= BUCKET_CAPACITY {
+                harvest.phase = return_to_headquarters(unit, dropoffs, nav)?;
+
+            } else if exhausted.contains(&cell) {
+                harvest.phase = next_resource(unit, &harvest, dropoffs, nav, |c| is_resource(c, exhausted))?;
+
+            } else {
+                harvest.phase = Phase::Gather(cell, 0);
+            }
+
+        },
+
+        Phase::Return(id) => if unit.is_idle() {
+
+            let p = unit.position();
+            let distance = dropoffs.iter().find(|b| b.id == id).map(|b| b.distance(p));
+            if distance.map(|d| d <= HEADQUARTERS_RADIUS).unwrap_or(false) {
+
+                if let Some(player) = players.get_mut(unit.owner.0 as usize) {
+                    player.stockpile.add(harvest.kind, harvest.carried as u32);
+                    player.stats.gathered += harvest.carried as u32;
+                }
+                harvest.carried = 0;
+
+                // Head back out to where we left off
+                let source = harvest.source;
+                let cell = find_resource(source, SEARCH_RADIUS, |c| is_resource(c, exhausted))?;
+                unit.move_to(nav, cell);
+                harvest.phase = Phase::Approach(cell);
+
+            // Headquarters was lost, look for another one
+            } else if distance.is_none() {
+                harvest.phase = return_to_headquarters(unit, dropoffs, nav)?;
+
+            // Headquarters cannot be reached
+            } else {
+                return None;
+            }
+
+        }
+
+    }
+
+    Some(harvest)
+
+}
+
+fn next_resource<F>(
+    unit: &mut Unit,
+    harvest: &Harvest,
+    dropoffs: &[&Building],
+    nav: &NavData,
+    is_resource: F
+
+) -> Option<Phase> where F: Fn(GridCell) -> bool {
+
+    if let Some(cell) = find_resource(harvest.source, SEARCH_RADIUS, is_resource) {
+        unit.move_to(nav, cell);
+        Some(Phase::Approach(cell))
+
+    } else if harvest.carried > 0 {
+        return_to_headquarters(unit, dropoffs, nav)
+
+    } else {
+        None
+    }
+
+}
+
+/// Sends the unit towards the closest drop off building of its owner.
+fn return_to_headquarters(unit: &mut Unit, dropoffs: &[&Building], nav: &NavData) -> Option<Phase> {
+    let (p, owner) = (unit.position(), unit.owner);
+    dropoffs.iter().filter(|b| b.owner == owner).min_by_key(|b| b.distance(p)).map(|b| {
+        unit.move_to(nav, b.origin());
+        Phase::Return(b.id)
+    })
+}
+
+/// Searches in rings of increasing distance around `cell` for a tile that
+/// matches the predicate.
+fn find_resource<F>(cell: GridCell, radius: i32, is_resource: F) -> Option<GridCell> where F: Fn(GridCell) -> bool {
+    for distance in 0..radius + 1 {
+        for y in cell.1 - distance..cell.1 + distance + 1 {
+            for x in cell.0 - distance..cell.0 + distance + 1 {
+                if x == cell.0 - distance || x == cell.0 + distance || y == cell.1 - distance || y == cell.1 + distance {
+                    let c = GridCell(x, y);
+                    if is_resource(c) {
+                        return Some(c);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn is_adjacent(a: GridCell, b: GridCell) -> bool {
+    (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1
+}
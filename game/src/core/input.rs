@@ -11,11 +11,20 @@
 use clockwork::Input;
 
 
+// Internal Dependencies ------------------------------------------------------
+use core::{BuildingID, BuildingKind, Target, UnitID, UnitKind};
+
+
 // Local Game Input -----------------------------------------------------------
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum GameInput {
     // TODO how to abstract unit commands etc?
-    LeftClick(u8, u8),
+    MoveUnit(UnitID, u8, u8),
+    PlaceBuilding(BuildingKind, u8, u8),
+    QueueUnit(BuildingID, UnitKind),
+    CancelUnit(BuildingID),
+    SetRally(BuildingID, u8, u8),
+    Attack(UnitID, Target),
     Idle
 }
 
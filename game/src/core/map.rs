@@ -0,0 +1,352 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::fs;
+use std::thread;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::path::{Component, Path, PathBuf};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{TileData, TileSet};
+
+
+// Statics --------------------------------------------------------------------
+pub const MAP_PORT_OFFSET: u16 = 1;
+const DOWNLOADS: &'static str = "downloads";
+const BUNDLE_MAGIC: &'static [u8; 4] = b"RTSB";
+const CHUNK_SIZE: usize = 4096;
+const MAX_BUNDLE_SIZE: usize = 16 * 1024 * 1024;
+
+
+// Map Bundles ----------------------------------------------------------------
+
+/// A map together with its tilesets and their images.
+///
+/// File names are relative to the asset directory, so a bundle written into
+/// another directory keeps all references between its files intact.
+#[derive(Debug, Clone)]
+pub struct MapBundle {
+    files: Vec<(String, Vec<u8>)>
+}
+
+impl MapBundle {
+
+    pub fn load(root: &Path, name: &str) -> io::Result<Self> {
+
+        let map = map_file(name);
+        let mut names = vec![map.clone()];
+
+        let data = TileData::new(&root.join(&map)).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+        })?;
+
+        for tileset in &data.tilesets {
+            let tileset = normalize(&Path::new(&map).with_file_name(&tileset.source))?;
+            let image = TileSet::image_source(&root.join(&tileset)).map_err(|err| {
+                io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+            })?;
+            let image = normalize(&Path::new(&tileset).with_file_name(image))?;
+            for name in vec![tileset, image] {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        let mut files = Vec::with_capacity(names.len());
+        for name in names {
+            let mut bytes = Vec::new();
+            fs::File::open(root.join(&name))?.read_to_end(&mut bytes)?;
+            files.push((name, bytes));
+        }
+
+        Ok(Self {
+            files: files
+        })
+
+    }
+
+    /// FNV-1a hash over the names and contents of all files, which is
+    /// stable across platforms and compiler versions.
+    pub fn hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for &(ref name, ref bytes) in &self.files {
+            for b in name.as_bytes().iter().chain(bytes.iter()) {
+                hash ^= *b as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    fn write(&self, root: &Path) -> io::Result<()> {
+        for &(ref name, ref bytes) in &self.files {
+            let path = root.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::File::create(path)?.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_u32(&mut bytes, self.files.len() as u32);
+        for &(ref name, ref data) in &self.files {
+            write_u32(&mut bytes, name.len() as u32);
+            bytes.extend_from_slice(name.as_bytes());
+            write_u32(&mut bytes, data.len() as u32);
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+
+        let mut offset = 0;
+        let mut files = Vec::new();
+        for _ in 0..read_u32(bytes, &mut offset)? {
+
+            let length = read_u32(bytes, &mut offset)? as usize;
+            let name = String::from_utf8(read_bytes(bytes, &mut offset, length)?.to_vec()).map_err(|_| invalid_data())?;
+
+            // Never write outside of the download directory
+            if normalize(Path::new(&name))? != name {
+                return Err(invalid_data());
+            }
+
+            let length = read_u32(bytes, &mut offset)? as usize;
+            files.push((name, read_bytes(bytes, &mut offset, length)?.to_vec()));
+
+        }
+
+        Ok(Self {
+            files: files
+        })
+
+    }
+
+}
+
+/// Returns the path of the map with the given hash, either within the local
+/// assets or a previously downloaded copy.
+pub fn find_map(root: &Path, name: &str, hash: u64) -> Option<PathBuf> {
+    let local = root.to_path_buf();
+    let downloaded = download_root(root, hash);
+    [local, downloaded].iter().find(|root| {
+        MapBundle::load(root, name).map(|b| b.hash() == hash).unwrap_or(false)
+
+    }).map(|root| root.join(map_file(name)))
+}
+
+
+// Map Server -----------------------------------------------------------------
+
+/// Serves a map bundle to all clients which connect during the lobby phase.
+pub fn serve_map(port: u16, bundle: MapBundle) -> io::Result<()> {
+
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let hash = bundle.hash();
+    let bytes = Arc::new(bundle.to_bytes());
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Ok(stream) = stream {
+                let bytes = bytes.clone();
+                thread::spawn(move || {
+                    if let Err(err) = send_bundle(stream, hash, &bytes) {
+                        println!("[Map] Failed to send map: {}", err);
+                    }
+                });
+            }
+        }
+    });
+
+    Ok(())
+
+}
+
+fn send_bundle(mut stream: TcpStream, hash: u64, bytes: &[u8]) -> io::Result<()> {
+
+    let mut header = BUNDLE_MAGIC.to_vec();
+    write_u32(&mut header, (hash >> 32) as u32);
+    write_u32(&mut header, hash as u32);
+    write_u32(&mut header, bytes.len() as u32);
+    stream.write_all(&header)?;
+
+    for chunk in bytes.chunks(CHUNK_SIZE) {
+        stream.write_all(chunk)?;
+    }
+
+    stream.flush()
+
+}
+
+
+// Map Downloads --------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub enum DownloadStatus {
+    /// Received and total number of bytes.
+    Pending(usize, usize),
+    /// Path of the downloaded map.
+    Done(PathBuf),
+    Failed(String)
+}
+
+/// Downloads a map bundle from the host in the background.
+pub struct MapDownload {
+    hash: u64,
+    status: Arc<Mutex<DownloadStatus>>
+}
+
+impl MapDownload {
+
+    /// Downloaded maps are stored below the given asset directory.
+    pub fn start(root: &Path, address: SocketAddr, name: &str, hash: u64) -> Self {
+
+        let status = Arc::new(Mutex::new(DownloadStatus::Pending(0, 0)));
+        let shared = status.clone();
+        let name = name.to_string();
+        let root = download_root(root, hash);
+
+        thread::spawn(move || {
+            let result = receive_bundle(address, hash, &shared).and_then(|bundle| {
+                bundle.write(&root)?;
+                Ok(root.join(map_file(&name)))
+            });
+
+            if let Ok(mut status) = shared.lock() {
+                *status = match result {
+                    Ok(path) => DownloadStatus::Done(path),
+                    Err(err) => {
+                        println!("[Map] Failed to download map: {}", err);
+                        DownloadStatus::Failed(err.to_string())
+                    }
+                };
+            }
+        });
+
+        Self {
+            hash: hash,
+            status: status
+        }
+
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn status(&self) -> DownloadStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_else(|_| {
+            DownloadStatus::Failed("download thread panicked".to_string())
+        })
+    }
+
+    pub fn map_path(&self) -> Option<PathBuf> {
+        match self.status() {
+            DownloadStatus::Done(path) => Some(path),
+            _ => None
+        }
+    }
+
+}
+
+fn receive_bundle(address: SocketAddr, hash: u64, status: &Mutex<DownloadStatus>) -> io::Result<MapBundle> {
+
+    let mut stream = TcpStream::connect(address)?;
+    let mut header = [0u8; 16];
+    stream.read_exact(&mut header)?;
+
+    let mut offset = 4;
+    let remote_hash = (read_u32(&header, &mut offset)? as u64) << 32 | read_u32(&header, &mut offset)? as u64;
+    let total = read_u32(&header, &mut offset)? as usize;
+    if &header[0..4] != BUNDLE_MAGIC || remote_hash != hash || total > MAX_BUNDLE_SIZE {
+        return Err(invalid_data());
+    }
+
+    let mut bytes = Vec::with_capacity(total);
+    let mut chunk = [0u8; CHUNK_SIZE];
+    while bytes.len() < total {
+        let count = stream.read(&mut chunk[..CHUNK_SIZE.min(total - bytes.len())])?;
+        if count == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed"));
+        }
+        bytes.extend_from_slice(&chunk[..count]);
+        if let Ok(mut status) = status.lock() {
+            *status = DownloadStatus::Pending(bytes.len(), total);
+        }
+    }
+
+    let bundle = MapBundle::from_bytes(&bytes)?;
+    if bundle.hash() == hash {
+        Ok(bundle)
+
+    } else {
+        Err(invalid_data())
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn map_file(name: &str) -> String {
+    format!("maps/{}.tmx", name)
+}
+
+fn download_root(root: &Path, hash: u64) -> PathBuf {
+    root.join(DOWNLOADS).join(format!("{:016x}", hash))
+}
+
+/// Resolves `.` and `..` without touching the file system, paths which
+/// leave the asset directory are rejected.
+fn normalize(path: &Path) -> io::Result<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {},
+            Component::ParentDir => if parts.pop().is_none() {
+                return Err(invalid_data());
+            },
+            _ => return Err(invalid_data())
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid map bundle")
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]);
+}
+
+fn read_u32(bytes: &[u8], offset: &mut usize) -> io::Result<u32> {
+    let b = read_bytes(bytes, offset, 4)?;
+    Ok((b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32)
+}
+
+fn read_bytes<'a>(bytes: &'a [u8], offset: &mut usize, length: usize) -> io::Result<&'a [u8]> {
+    if *offset + length <= bytes.len() {
+        let slice = &bytes[*offset..*offset + length];
+        *offset += length;
+        Ok(slice)
+
+    } else {
+        Err(invalid_data())
+    }
+}
+
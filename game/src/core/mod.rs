@@ -8,13 +8,40 @@
 
 
 // Modules --------------------------------------------------------------------
+mod ai;
+mod assets;
+mod building;
+mod combat;
+mod definitions;
+mod fog;
+mod harvest;
 mod input;
+mod map;
+mod movement;
+mod occupancy;
 mod options;
+mod player;
+mod resource;
 mod state;
+mod unit;
+mod victory;
 
 
 // Re-Exports -----------------------------------------------------------------
+pub use self::ai::{Ai, Difficulty};
+pub use self::assets::{Assets, Handle, Reloaded};
+pub use self::building::{Building, BuildingID};
+pub use self::combat::{Projectile, Target};
+pub use self::definitions::{AttackDefinition, BuildingDefinition, BuildingKind, Definitions, UnitDefinition, UnitKind};
+pub use self::fog::Fog;
+pub use self::harvest::Harvest;
 pub use self::input::GameInput;
-pub use self::options::GameOptions;
+pub use self::map::{MAP_PORT_OFFSET, DownloadStatus, MapBundle, MapDownload, find_map, serve_map};
+pub use self::occupancy::Occupancy;
+pub use self::options::{GameOptions, Victory};
+pub use self::player::{Player, PlayerID, PlayerStats};
+pub use self::resource::{ResourceKind, ResourceNode, Stockpile};
 pub use self::state::GameState;
+pub use self::unit::{Unit, UnitID};
+pub use self::victory::Outcome;
 
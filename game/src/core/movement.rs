@@ -0,0 +1,92 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, NavData};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{Occupancy, Unit};
+
+
+// Local Avoidance ------------------------------------------------------------
+
+/// Advances the movement of all units by one tick.
+///
+/// `units` must be sorted by their ID so that all clients resolve conflicting
+/// reservations in the same order.
+pub fn tick(units: &mut [Unit], occupancy: &mut Occupancy, nav: &NavData) {
+
+    // Complete any steps which finish during this tick
+    for unit in units.iter_mut() {
+        unit.advance_step(occupancy);
+    }
+
+    // Start new steps
+    for i in 0..units.len() {
+
+        let desired = if let Some(cell) = units[i].desired_cell() {
+            cell
+
+        } else {
+            continue;
+        };
+
+        // Terrain changed or a building was placed since the path was found
+        if !nav.is_traversable(desired.0, desired.1) {
+            units[i].wait(nav, occupancy);
+            continue;
+        }
+
+        match occupancy.get(desired) {
+            None => units[i].start_step(occupancy),
+
+            Some(id) if id == units[i].id => units[i].start_step(occupancy),
+
+            Some(id) => {
+
+                let j = units.binary_search_by_key(&id, |u| u.id).expect("Occupancy references unknown unit");
+
+                // Head-on encounter, both units simply swap their tiles
+                if units[j].desired_cell() == Some(units[i].position()) {
+                    units[i].start_swap();
+                    units[j].start_swap();
+
+                // Our goal is taken by a unit that is not going anywhere
+                } else if units[i].is_final_step() && units[j].is_idle() {
+                    units[i].stop();
+
+                } else {
+                    units[i].wait(nav, occupancy);
+                }
+
+            }
+        }
+
+    }
+
+}
+
+/// Returns the closest free and traversable cell around `cell`, searching in
+/// rings of increasing distance up to `radius`.
+pub fn free_cell_near(nav: &NavData, occupancy: &Occupancy, cell: GridCell, radius: i32) -> Option<GridCell> {
+    for distance in 0..radius + 1 {
+        for y in cell.1 - distance..cell.1 + distance + 1 {
+            for x in cell.0 - distance..cell.0 + distance + 1 {
+                if x == cell.0 - distance || x == cell.0 + distance || y == cell.1 - distance || y == cell.1 + distance {
+                    let c = GridCell(x, y);
+                    if nav.is_traversable(x, y) && occupancy.is_free(c) {
+                        return Some(c);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
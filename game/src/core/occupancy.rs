@@ -0,0 +1,84 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::iter;
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::GridCell;
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::UnitID;
+
+
+// Tile Occupancy Reservations ------------------------------------------------
+
+/// Tracks which unit currently stands on, or has reserved, each map tile.
+///
+/// A moving unit holds both the tile it is leaving and the tile it is moving
+/// onto until its step is completed.
+#[derive(Debug)]
+pub struct Occupancy {
+    width: u32,
+    height: u32,
+    cells: Vec<Option<UnitID>>
+}
+
+impl Occupancy {
+
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width,
+            height: height,
+            cells: iter::repeat(None).take((width * height) as usize).collect()
+        }
+    }
+
+    pub fn get(&self, cell: GridCell) -> Option<UnitID> {
+        self.offset(cell).and_then(|i| self.cells[i])
+    }
+
+    pub fn is_free(&self, cell: GridCell) -> bool {
+        self.offset(cell).map(|i| self.cells[i].is_none()).unwrap_or(false)
+    }
+
+    pub fn reserve(&mut self, cell: GridCell, id: UnitID) {
+        if let Some(i) = self.offset(cell) {
+            self.cells[i] = Some(id);
+        }
+    }
+
+    /// Releases the tile, but only in case it is still held by `id`.
+    pub fn release(&mut self, cell: GridCell, id: UnitID) {
+        if let Some(i) = self.offset(cell) {
+            if self.cells[i] == Some(id) {
+                self.cells[i] = None;
+            }
+        }
+    }
+
+    fn offset(&self, cell: GridCell) -> Option<usize> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        if cell.0 >= 0 && cell.0 < w && cell.1 >= 0 && cell.1 < h {
+            Some((cell.1 * w + cell.0) as usize)
+
+        } else {
+            None
+        }
+    }
+
+}
+
+impl Default for Occupancy {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
@@ -11,11 +11,24 @@
 use clockwork::Options;
 
 
+// Internal Dependencies ------------------------------------------------------
+use core::Difficulty;
+
+
 // Local Game Options ---------------------------------------------------------
 #[derive(Debug, Hash, Clone, Serialize, Deserialize)]
 pub struct GameOptions {
     pub min_players: u8,
-    pub random_seed: [u8; 4]
+    pub random_seed: [u8; 4],
+    pub victory: Victory,
+    pub ai_players: u8,
+    pub ai_difficulty: Difficulty,
+    /// Generates the map from `random_seed` instead of loading it.
+    pub generate_map: bool,
+    /// Name of the map, padded with zeros to a fixed size.
+    pub map_name: [u8; 32],
+    /// Content hash of the map and all files it depends on.
+    pub map_hash: u64
 }
 
 impl Options for GameOptions {}
@@ -24,7 +37,61 @@ impl Default for GameOptions {
     fn default() -> Self {
         Self {
             min_players: 3,
-            random_seed: [0, 0, 0, 0]
+            random_seed: [0, 0, 0, 0],
+            victory: Victory::default(),
+            ai_players: 0,
+            ai_difficulty: Difficulty::Normal,
+            generate_map: false,
+            map_name: [0; 32],
+            map_hash: 0
+        }
+    }
+}
+
+impl GameOptions {
+
+    pub fn map_name(&self) -> String {
+        let length = self.map_name.iter().position(|b| *b == 0).unwrap_or(self.map_name.len());
+        String::from_utf8_lossy(&self.map_name[..length]).into_owned()
+    }
+
+    /// Sets the map name, names longer than 32 bytes are truncated.
+    pub fn set_map_name(&mut self, name: &str) {
+        self.map_name = [0; 32];
+        for (i, b) in name.bytes().take(32).enumerate() {
+            self.map_name[i] = b;
+        }
+    }
+
+}
+
+
+// Victory Conditions ---------------------------------------------------------
+
+/// The conditions under which a match ends.
+///
+/// Options are split into equally sized chunks by the network layer, so all
+/// fields have a fixed size and limits of `0` disable the condition.
+#[derive(Debug, Hash, Copy, Clone, Serialize, Deserialize)]
+pub struct Victory {
+    /// Players lose once all of their units and buildings are gone.
+    pub annihilation: bool,
+    /// Players lose once their headquarters have been destroyed.
+    pub headquarters: bool,
+    /// The first player to gather this many resources in total wins.
+    pub resources: u32,
+    /// The match ends after this many ticks, the surviving players which
+    /// gathered the most resources win.
+    pub time_limit: u32
+}
+
+impl Default for Victory {
+    fn default() -> Self {
+        Self {
+            annihilation: true,
+            headquarters: false,
+            resources: 0,
+            time_limit: 0
         }
     }
 }
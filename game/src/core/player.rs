@@ -0,0 +1,55 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use clockwork::ConnectionID;
+use tiles::GridCell;
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::Stockpile;
+
+
+// Player Abstraction ---------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct PlayerID(pub u8);
+
+#[derive(Debug)]
+pub struct Player {
+    pub id: PlayerID,
+    /// Computer players have no connection.
+    pub connection: Option<ConnectionID>,
+    pub start: GridCell,
+    pub stockpile: Stockpile,
+    pub stats: PlayerStats,
+    pub defeated: bool
+}
+
+impl Player {
+    pub fn new(id: PlayerID, connection: Option<ConnectionID>, start: GridCell) -> Self {
+        Self {
+            id: id,
+            connection: connection,
+            start: start,
+            stockpile: Stockpile::default(),
+            stats: PlayerStats::default(),
+            defeated: false
+        }
+    }
+}
+
+
+// Player Statistics ----------------------------------------------------------
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PlayerStats {
+    pub gathered: u32,
+    pub units_produced: u32,
+    pub units_lost: u32,
+    pub buildings_lost: u32
+}
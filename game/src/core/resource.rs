@@ -0,0 +1,123 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Statics --------------------------------------------------------------------
+const NODE_AMOUNT_WOOD: u16 = 40;
+const NODE_AMOUNT_STONE: u16 = 60;
+
+
+// Resource Types -------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceKind {
+    Wood,
+    Stone
+}
+
+impl ResourceKind {
+    pub fn from_terrain(name: &str) -> Option<Self> {
+        match name {
+            "Forest" => Some(ResourceKind::Wood),
+            "Rocks" => Some(ResourceKind::Stone),
+            _ => None
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Wood" => Some(ResourceKind::Wood),
+            "Stone" => Some(ResourceKind::Stone),
+            _ => None
+        }
+    }
+}
+
+
+// Resource Nodes -------------------------------------------------------------
+
+/// The remaining resources of a single map tile.
+///
+/// Nodes are only created once a unit first gathers from a tile, or up front
+/// for resources placed on the map, and removed again once they have been
+/// exhausted.
+#[derive(Debug)]
+pub struct ResourceNode {
+    pub kind: ResourceKind,
+    pub amount: u16
+}
+
+impl ResourceNode {
+
+    pub fn new(kind: ResourceKind) -> Self {
+        Self {
+            kind: kind,
+            amount: match kind {
+                ResourceKind::Wood => NODE_AMOUNT_WOOD,
+                ResourceKind::Stone => NODE_AMOUNT_STONE
+            }
+        }
+    }
+
+    pub fn with_amount(kind: ResourceKind, amount: u16) -> Self {
+        Self {
+            kind: kind,
+            amount: amount
+        }
+    }
+
+    /// Removes up to `amount` resources from the node and returns how many
+    /// were actually taken.
+    pub fn take(&mut self, amount: u16) -> u16 {
+        let taken = amount.min(self.amount);
+        self.amount -= taken;
+        taken
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.amount == 0
+    }
+
+}
+
+
+// Player Stockpiles ----------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct Stockpile {
+    wood: u32,
+    stone: u32
+}
+
+impl Stockpile {
+
+    pub fn get(&self, kind: ResourceKind) -> u32 {
+        match kind {
+            ResourceKind::Wood => self.wood,
+            ResourceKind::Stone => self.stone
+        }
+    }
+
+    pub fn add(&mut self, kind: ResourceKind, amount: u32) {
+        match kind {
+            ResourceKind::Wood => self.wood += amount,
+            ResourceKind::Stone => self.stone += amount
+        }
+    }
+
+    /// Deducts the given amounts in case all of them are available.
+    pub fn spend(&mut self, wood: u32, stone: u32) -> bool {
+        if self.wood >= wood && self.stone >= stone {
+            self.wood -= wood;
+            self.stone -= stone;
+            true
+
+        } else {
+            false
+        }
+    }
+
+}
@@ -8,6 +8,8 @@
 
 
 // STD Dependencies -----------------------------------------------------------
+use std::mem;
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
@@ -15,13 +17,24 @@ use std::path::{Path, PathBuf};
 // External Dependencies ------------------------------------------------------
 use rand::{XorShiftRng, SeedableRng, Rng};
 use audio::AudioQueue;
-use renderer::RenderTarget;
-use tiles::{TileData, TerrainGrid, TileSet};
+use renderer::{Factory, RenderTarget};
+use tiles::{GeneratorOptions, GridCell, MapObjects, NavData, TileData, TerrainGrid, TileSet, TriggerArea};
 use clockwork::{ConnectionID, HostID, State};
 
 
 // Internal Dependencies ------------------------------------------------------
-use core::{GameInput, GameOptions};
+use core::{building, combat, harvest, movement, victory, Ai, Building, BuildingID, BuildingKind, Definitions, Fog, GameInput};
+use core::{GameOptions, Occupancy, Outcome, Player, PlayerID, Projectile, ResourceKind, ResourceNode, Target, Unit, UnitID, UnitKind};
+use core::{Assets, DownloadStatus, Handle, MapDownload, Reloaded, find_map};
+
+
+// Statics --------------------------------------------------------------------
+const UNITS_PER_PLAYER: usize = 4;
+const START_INSET: i32 = 8;
+const SPAWN_RADIUS: i32 = 6;
+const EXIT_RADIUS: i32 = 3;
+const START_WOOD: u32 = 60;
+const START_STONE: u32 = 20;
 
 
 // Game State Abstraction -----------------------------------------------------
@@ -30,16 +43,36 @@ pub struct GameState {
     options: GameOptions,
     rng: XorShiftRng,
     audio: AudioQueue,
+    assets: Assets,
+    tilesets: Vec<Handle<TileSet>>,
+    definitions: Definitions,
+    nav: NavData,
+    occupancy: Occupancy,
+    fog: Fog,
+    players: Vec<Player>,
+    ais: Vec<Ai>,
+    resources: BTreeMap<GridCell, ResourceNode>,
+    buildings: Vec<Building>,
+    units: Vec<Unit>,
+    projectiles: Vec<Projectile>,
+    next_building_id: u16,
+    next_unit_id: u16,
+    ticks: u32,
+    outcome: Option<Outcome>,
+    triggers: Vec<TriggerArea>,
+    map: Option<PathBuf>,
+    map_host: Option<SocketAddr>,
+    download: Option<MapDownload>,
     pub terrain: Option<TerrainGrid>
 }
 
 impl State<GameOptions, GameInput, RenderTarget> for GameState {
 
     fn is_ready(&self) -> bool {
-        self.is_ready
+        self.is_ready && (self.options.generate_map || self.map_path().is_some())
     }
 
-    fn init(&mut self, host_id: HostID, _: &[(ConnectionID, SocketAddr)], target: &mut RenderTarget) {
+    fn init(&mut self, host_id: HostID, connections: &[(ConnectionID, SocketAddr)], target: &mut RenderTarget) {
 
         // Seed RNG
         println!("[GameState] (Host {:?}) Seeding rng with {:?}", host_id, self.options.random_seed);
@@ -50,11 +83,61 @@ impl State<GameOptions, GameInput, RenderTarget> for GameState {
             self.options.random_seed[3] as u32
         ]);
 
+        // Building and unit definitions
+        let definitions = self.assets.path("data/definitions.toml");
+        self.definitions = Definitions::load(&definitions).expect("[GameState] Failed to load definitions.");
+
         // Setup Map rendering
         println!("[GameState] (Host {:?}) Loading map...", host_id);
 
-        // TODO cleanup
-        let ts = TileSet::new(&mut target.factory, Path::new("../assets/maps/develop.tsx")).unwrap();
+        let mut m = if self.options.generate_map {
+
+            // All peers agreed on the seed, so they all end up with the
+            // same map
+            let options = GeneratorOptions {
+                players: connections.len() + self.options.ai_players as usize,
+                .. GeneratorOptions::default()
+            };
+            let path = self.assets.path("maps").join(&options.tileset);
+            let handle = self.load_tileset(target, &path);
+            let seed = self.options.random_seed;
+            let m = TileData::generate(self.assets.tileset(handle), [seed[0] as u32, seed[1] as u32, seed[2] as u32, seed[3] as u32], &options).unwrap_or_else(|err| {
+                panic!("[GameState] Failed to generate map: {}", err)
+            });
+            self.tilesets = vec![handle];
+            m
+
+        } else {
+
+            // Tilesets are relative to the map, which might have been
+            // downloaded from the host
+            let map_path = self.map_path().expect("[GameState] Map is not available.");
+            let handle = self.assets.load_map(&map_path).unwrap_or_else(|err| {
+                panic!("[GameState] Failed to load map {:?}: {}", map_path, err)
+            });
+            let m = self.assets.map(handle).clone();
+            if m.tilesets.is_empty() {
+                panic!("[GameState] Map {:?} has no tileset.", map_path);
+            }
+
+            // Decoration layers may use additional tilesets
+            let map_dir = map_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+            let tilesets = m.tilesets.iter().map(|t| {
+                self.load_tileset(target, &map_dir.join(&t.source))
+
+            }).collect();
+            self.tilesets = tilesets;
+            m
+
+        };
+        let objects = mem::replace(&mut m.objects, MapObjects::default());
+
+        let ts = self.assets.tileset(self.tilesets[0]).clone();
+        let layer_tilesets = self.tilesets[1..].iter().map(|h| {
+            self.assets.tileset(*h).clone()
+
+        }).collect();
+
         let mut terrain = TerrainGrid::new(
             &mut target.factory,
             target.color.clone(),
@@ -62,19 +145,145 @@ impl State<GameOptions, GameInput, RenderTarget> for GameState {
             target.height,
             32,
             ts
-        );
 
-        let m = TileData::new(Path::new("../assets/maps/develop.tmx"));
+        ).with_layers(
+            &mut target.factory,
+            target.color.clone(),
+            &m.layers,
+            layer_tilesets
+
+        ).with_overlay(&mut target.factory, target.color.clone());
+
         terrain.set_source(m);
+
+        // Setup navigation and unit reservations
+        let (width, height) = (terrain.source().width, terrain.source().height);
+        self.nav = NavData::new(width, height);
+        self.nav.initialize(terrain.source(), terrain.tileset());
+        self.occupancy = Occupancy::new(width, height);
         self.terrain = Some(terrain);
 
+        // Every player needs a start location of their own
+        let starts = start_locations(&objects, width as i32, height as i32);
+        let count = connections.len() + self.options.ai_players as usize;
+        if count > starts.len() {
+            panic!("[GameState] Map only has {} start location(s) for {} players.", starts.len(), count);
+        }
+
+        // Players are ordered by their connection to keep all clients in sync
+        let mut ids: Vec<ConnectionID> = connections.iter().map(|&(id, _)| id).collect();
+        ids.sort();
+        self.players = ids.into_iter().enumerate().map(|(i, id)| {
+            Player::new(PlayerID(i as u8), Some(id), starts[i])
+
+        }).collect();
+
+        // Computer players follow after all remote ones
+        for _ in 0..self.options.ai_players {
+            let i = self.players.len();
+            let id = PlayerID(i as u8);
+            let seed = [self.rng.gen::<u32>() | 1, self.rng.gen(), self.rng.gen(), self.rng.gen()];
+            self.players.push(Player::new(id, None, starts[i]));
+            self.ais.push(Ai::new(id, self.options.ai_difficulty, seed));
+        }
+
+        // Resource amounts placed on the map
+        for spawn in &objects.resources {
+            if let Some(kind) = ResourceKind::from_name(&spawn.resource) {
+                let node = spawn.amount.map(|a| ResourceNode::with_amount(kind, a)).unwrap_or_else(|| ResourceNode::new(kind));
+                self.resources.insert(spawn.cell, node);
+            }
+        }
+
+        self.triggers = objects.triggers;
+
+        println!("[GameState] (Host {:?}) Spawning bases for {} player(s)...", host_id, self.players.len());
+        self.spawn_start_bases();
+
+        // Fog of war
+        self.fog = Fog::new(width, height, self.players.len());
+        if let Some(ref terrain) = self.terrain {
+            self.fog.update_terrain(terrain.source(), terrain.tileset());
+        }
+        self.fog.tick(&self.units);
+
         println!("[GameState] (Host {:?}) Initialized", host_id);
         self.is_ready = true;
 
     }
 
     fn tick(&mut self, _: u64, _: HostID, _: &[(ConnectionID, SocketAddr)]) {
-        println!("[GameState] Tick");
+
+        // Computer players decide based on the state of the previous tick
+        let mut ais = mem::replace(&mut self.ais, Vec::new());
+        let mut commands = Vec::new();
+        for ai in &mut ais {
+            for input in ai.think(self) {
+                commands.push((ai.player, input));
+            }
+        }
+        self.ais = ais;
+
+        for (owner, input) in commands {
+            self.apply_command(owner, input);
+        }
+
+        for building in &mut self.buildings {
+            building.construct();
+        }
+
+        self.tick_production();
+
+        let exhausted = if let Some(ref terrain) = self.terrain {
+            harvest::tick(
+                &mut self.units,
+                &mut self.resources,
+                &mut self.players,
+                &self.buildings,
+                &self.definitions,
+                terrain,
+                &self.nav
+            )
+
+        } else {
+            Vec::new()
+        };
+
+        // Only fully exhausted resources reflow the terrain
+        for cell in exhausted {
+            self.consume_tile(cell.0, cell.1);
+        }
+
+        combat::tick(
+            &mut self.units,
+            &mut self.buildings,
+            &mut self.projectiles,
+            &self.definitions,
+            &self.nav,
+            &mut self.rng
+        );
+        self.remove_dead();
+
+        movement::tick(&mut self.units, &mut self.occupancy, &self.nav);
+        self.fog.tick(&self.units);
+
+        self.ticks += 1;
+        self.outcome = victory::check(
+            &self.options.victory,
+            self.ticks,
+            &mut self.players,
+            &self.units,
+            &self.buildings,
+            &self.definitions
+        );
+
+        if let Some(ref outcome) = self.outcome {
+            println!("[GameState] Game over after {} ticks, winners: {:?}", outcome.tick, outcome.winners);
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.outcome.is_some()
     }
 
     fn apply_options(&mut self, host_id: HostID, options: &[(ConnectionID, GameOptions)]) {
@@ -83,6 +292,7 @@ impl State<GameOptions, GameInput, RenderTarget> for GameState {
                 self.is_ready = true;
                 println!("take options {:?}", options);
                 self.options = o.clone();
+                self.select_map();
                 break;
             }
         }
@@ -90,9 +300,8 @@ impl State<GameOptions, GameInput, RenderTarget> for GameState {
 
     fn apply_input(&mut self, _: HostID, id: ConnectionID, input: GameInput) {
         println!("[GameState] [Input] [#{:?}] {:?}", id, input);
-        match input {
-            GameInput::LeftClick(x, y) => self.consume_tile(x as i32, y as i32),
-            GameInput::Idle => {}
+        if let Some(owner) = self.player_id(id) {
+            self.apply_command(owner, input);
         }
     }
 
@@ -100,15 +309,450 @@ impl State<GameOptions, GameInput, RenderTarget> for GameState {
 
 impl GameState {
 
+    /// Replaces the assets, e.g. to load them from another directory.
+    pub fn set_assets(&mut self, assets: Assets) {
+        self.assets = assets;
+    }
+
+    pub fn assets(&mut self) -> &mut Assets {
+        &mut self.assets
+    }
+
+    /// Reloads changed assets and swaps the tilesets of the terrain.
+    ///
+    /// The base tileset is only swapped for drawing, terrain, navigation and
+    /// fog of war keep the tile types and terrains the game started with so
+    /// all clients stay in sync.
+    pub fn reload_assets(&mut self, factory: &mut Factory) -> Vec<Reloaded> {
+        let reloaded = self.assets.reload(factory);
+        if let Some(ref mut terrain) = self.terrain {
+            for r in &reloaded {
+                if let Reloaded::TileSet(handle) = *r {
+                    for (i, _) in self.tilesets.iter().enumerate().filter(|&(_, h)| *h == handle) {
+                        let tileset = self.assets.tileset(handle).clone();
+                        if i == 0 {
+                            terrain.set_display_tileset(tileset);
+
+                        } else {
+                            terrain.set_tileset(i, tileset);
+                        }
+                    }
+                }
+            }
+        }
+        reloaded
+    }
+
+    /// Address from which missing maps are downloaded.
+    pub fn set_map_host(&mut self, address: SocketAddr) {
+        self.map_host = Some(address);
+    }
+
+    pub fn download_status(&self) -> Option<DownloadStatus> {
+        self.download.as_ref().map(|d| d.status())
+    }
+
+    pub fn units(&self) -> &[Unit] {
+        &self.units
+    }
+
+    pub fn buildings(&self) -> &[Building] {
+        &self.buildings
+    }
+
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    pub fn can_place_building(&self, kind: BuildingKind, origin: GridCell) -> bool {
+        match (self.definitions.building(kind), self.terrain.as_ref()) {
+            (Some(definition), Some(terrain)) => {
+                building::can_place(definition, origin, terrain, &self.occupancy, &self.buildings)
+            },
+            _ => false
+        }
+    }
+
+    /// Returns whether the player has completed all of the required buildings.
+    pub fn has_requirements(&self, owner: PlayerID, requires: &[BuildingKind]) -> bool {
+        requires.iter().all(|kind| {
+            self.buildings.iter().any(|b| b.owner == owner && b.kind == *kind && b.is_complete())
+        })
+    }
+
+    pub fn projectiles(&self) -> &[Projectile] {
+        &self.projectiles
+    }
+
+    pub fn ticks(&self) -> u32 {
+        self.ticks
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    /// Named areas which were placed on the map.
+    pub fn triggers(&self) -> &[TriggerArea] {
+        &self.triggers
+    }
+
+    pub fn fog(&self) -> &Fog {
+        &self.fog
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn player_id(&self, connection: ConnectionID) -> Option<PlayerID> {
+        self.players.iter().find(|p| p.connection == Some(connection)).map(|p| p.id)
+    }
+
+    /// Uses a local copy of the map selected by the host, or starts to
+    /// download it from the host in case there is none.
+    fn select_map(&mut self) {
+
+        if self.options.generate_map {
+            return;
+        }
+
+        let (name, hash) = (self.options.map_name(), self.options.map_hash);
+        self.map = find_map(self.assets.root(), &name, hash);
+
+        let downloading = self.download.as_ref().map(|d| d.hash() == hash).unwrap_or(false);
+        if self.map.is_none() && !downloading {
+            if let Some(address) = self.map_host {
+                println!("[GameState] Map \"{}\" ({:016x}) is missing, downloading it from {:?}...", name, hash, address);
+                self.download = Some(MapDownload::start(self.assets.root(), address, &name, hash));
+
+            } else {
+                println!("[GameState] Map \"{}\" ({:016x}) is missing and there is no host to download it from.", name, hash);
+            }
+        }
+
+    }
+
+    fn map_path(&self) -> Option<PathBuf> {
+        self.map.clone().or_else(|| self.download.as_ref().and_then(|d| d.map_path()))
+    }
+
+    fn load_tileset(&mut self, target: &mut RenderTarget, path: &Path) -> Handle<TileSet> {
+        self.assets.load_tileset(&mut target.factory, path).unwrap_or_else(|err| {
+            panic!("[GameState] Failed to load tileset {:?}: {}", path, err)
+        })
+    }
+
+    /// Applies an input on behalf of a player, both remote and computer
+    /// players issue their commands through here.
+    fn apply_command(&mut self, owner: PlayerID, input: GameInput) {
+        match input {
+            GameInput::MoveUnit(unit, x, y) => {
+                let (nav, terrain, definitions) = (&self.nav, &self.terrain, &self.definitions);
+                if let Some(unit) = self.units.iter_mut().find(|u| u.id == unit && u.owner == owner) {
+
+                    // Moving onto a resource starts harvesting it
+                    let cell = GridCell(x as i32, y as i32);
+                    let harvester = definitions.unit(unit.kind).map(|d| d.harvester).unwrap_or(false);
+                    let harvesting = harvester && terrain.as_ref().map(|t| harvest::start(unit, t, nav, cell)).unwrap_or(false);
+                    if !harvesting {
+                        unit.harvest = None;
+                        unit.move_to(nav, cell);
+                    }
+
+                    // Explicit moves cancel any attack
+                    unit.target = None;
+                    unit.pursue = false;
+
+                }
+            },
+            GameInput::PlaceBuilding(kind, x, y) => {
+                self.place_building(owner, kind, GridCell(x as i32, y as i32));
+            },
+            GameInput::QueueUnit(building, kind) => {
+                self.queue_unit(owner, building, kind);
+            },
+            GameInput::CancelUnit(building) => {
+                self.cancel_unit(owner, building);
+            },
+            GameInput::SetRally(building, x, y) => {
+                if let Some(b) = self.buildings.iter_mut().find(|b| b.id == building && b.owner == owner) {
+                    b.rally = Some(GridCell(x as i32, y as i32));
+                }
+            },
+            GameInput::Attack(unit, target) => {
+                let enemy = match target {
+                    Target::Unit(t) => self.units.iter().any(|u| u.id == t && u.owner != owner),
+                    Target::Building(t) => self.buildings.iter().any(|b| b.id == t && b.owner != owner)
+                };
+                if enemy {
+                    if let Some(unit) = self.units.iter_mut().find(|u| u.id == unit && u.owner == owner) {
+                        combat::attack(unit, target);
+                    }
+                }
+            },
+            GameInput::Idle => {}
+        }
+    }
+
+    fn spawn_start_bases(&mut self) {
+
+        let starts: Vec<(PlayerID, GridCell)> = self.players.iter().map(|p| (p.id, p.start)).collect();
+        for (owner, start) in starts {
+
+            // Headquarters are centered on the start location
+            let headquarters = self.definitions.building_kind("Headquarters").and_then(|kind| {
+                self.definitions.building(kind).map(|d| {
+                    let (w, h) = d.footprint;
+                    let origin = GridCell(start.0 - w / 2, start.1 - h / 2);
+                    Building::completed(BuildingID(self.next_building_id), d, kind, owner, origin)
+                })
+            });
+
+            if let Some(building) = headquarters {
+                self.add_building(building);
+            }
+
+            if let Some(player) = self.players.get_mut(owner.0 as usize) {
+                player.stockpile.add(ResourceKind::Wood, START_WOOD);
+                player.stockpile.add(ResourceKind::Stone, START_STONE);
+            }
+
+            if let Some(worker) = self.definitions.unit_kind("Worker") {
+                for _ in 0..UNITS_PER_PLAYER {
+                    if let Some(cell) = movement::free_cell_near(&self.nav, &self.occupancy, start, SPAWN_RADIUS) {
+                        self.spawn_unit(owner, worker, cell);
+                    }
+                }
+            }
+        }
+
+    }
+
+    fn place_building(&mut self, owner: PlayerID, kind: BuildingKind, origin: GridCell) {
+
+        if !self.can_place_building(kind, origin) {
+            println!("[GameState] Cannot place {:?} at {:?}", kind, origin);
+            return;
+        }
+
+        let building = if let Some(definition) = self.definitions.building(kind) {
+            if !self.has_requirements(owner, &definition.requires) {
+                println!("[GameState] Player {:?} lacks the requirements for {:?}", owner, kind);
+                return;
+            }
+
+            let (wood, stone) = definition.cost;
+            let paid = self.players.get_mut(owner.0 as usize).map(|p| p.stockpile.spend(wood, stone)).unwrap_or(false);
+            if paid {
+                Some(Building::new(BuildingID(self.next_building_id), definition, kind, owner, origin))
+
+            } else {
+                None
+            }
+
+        } else {
+            return;
+        };
+
+        if let Some(building) = building {
+            self.add_building(building);
+
+        } else {
+            println!("[GameState] Player {:?} cannot afford {:?}", owner, kind);
+        }
+
+    }
+
+    fn add_building(&mut self, building: Building) {
+        self.next_building_id += 1;
+        for cell in building.cells() {
+            self.nav.block_tile(cell.0, cell.1);
+        }
+        self.buildings.push(building);
+    }
+
+    /// Rebuilds navigation data from the terrain and blocks all tiles which
+    /// are covered by buildings.
+    fn update_nav(&mut self) {
+        if let Some(ref terrain) = self.terrain {
+            self.nav.initialize(terrain.source(), terrain.tileset());
+        }
+        for building in &self.buildings {
+            for cell in building.cells() {
+                self.nav.block_tile(cell.0, cell.1);
+            }
+        }
+    }
+
+    fn queue_unit(&mut self, owner: PlayerID, building: BuildingID, kind: UnitKind) {
+
+        let (cost, requires) = if let Some(definition) = self.definitions.unit(kind) {
+            (definition.cost, definition.requires.clone())
+
+        } else {
+            return;
+        };
+
+        // The building must be able to produce this kind of unit
+        let producible = self.buildings.iter().find(|b| b.id == building && b.owner == owner).and_then(|b| {
+            self.definitions.building(b.kind)
+
+        }).map(|d| d.produces.contains(&kind)).unwrap_or(false);
+
+        if !producible || !self.has_requirements(owner, &requires) {
+            return;
+        }
+
+        let (wood, stone) = cost;
+        let player = if let Some(player) = self.players.get_mut(owner.0 as usize) {
+            player
+
+        } else {
+            return;
+        };
+
+        if let Some(b) = self.buildings.iter_mut().find(|b| b.id == building) {
+            if player.stockpile.spend(wood, stone) && !b.queue_unit(kind) {
+                // Queue was full, refund right away
+                player.stockpile.add(ResourceKind::Wood, wood);
+                player.stockpile.add(ResourceKind::Stone, stone);
+            }
+        }
+
+    }
+
+    fn cancel_unit(&mut self, owner: PlayerID, building: BuildingID) {
+
+        let canceled = self.buildings.iter_mut().find(|b| b.id == building && b.owner == owner).and_then(|b| {
+            b.cancel_unit()
+        });
+
+        // Refund the full cost
+        let cost = canceled.and_then(|kind| self.definitions.unit(kind)).map(|d| d.cost);
+        if let Some((wood, stone)) = cost {
+            if let Some(player) = self.players.get_mut(owner.0 as usize) {
+                player.stockpile.add(ResourceKind::Wood, wood);
+                player.stockpile.add(ResourceKind::Stone, stone);
+            }
+        }
+
+    }
+
+    fn tick_production(&mut self) {
+
+        let mut ready = Vec::new();
+        let definitions = &self.definitions;
+        for b in &mut self.buildings {
+            let build_ticks = b.queue().front().and_then(|kind| definitions.unit(*kind)).map(|d| d.build_ticks);
+            if let Some(build_ticks) = build_ticks {
+                if let Some(kind) = b.advance_production(build_ticks) {
+                    ready.push((b.id, kind));
+                }
+            }
+        }
+
+        for (id, kind) in ready {
+
+            let (owner, origin, footprint, rally) = if let Some(b) = self.buildings.iter().find(|b| b.id == id) {
+                (b.owner, b.origin(), b.footprint(), b.rally)
+
+            } else {
+                continue;
+            };
+
+            // Units leave towards the closest free tile around the building
+            let center = GridCell(origin.0 + footprint.0 / 2, origin.1 + footprint.1 / 2);
+            let radius = footprint.0.max(footprint.1) / 2 + EXIT_RADIUS;
+            if let Some(cell) = movement::free_cell_near(&self.nav, &self.occupancy, center, radius) {
+
+                let unit = self.spawn_unit(owner, kind, cell);
+                if let Some(player) = self.players.get_mut(owner.0 as usize) {
+                    player.stats.units_produced += 1;
+                }
+                if let (Some(unit), Some(rally)) = (unit, rally) {
+                    let nav = &self.nav;
+                    if let Some(u) = self.units.iter_mut().find(|u| u.id == unit) {
+                        u.move_to(nav, rally);
+                    }
+                }
+
+                if let Some(b) = self.buildings.iter_mut().find(|b| b.id == id) {
+                    b.finish_production();
+                }
+
+            }
+
+        }
+
+    }
+
+    fn spawn_unit(&mut self, owner: PlayerID, kind: UnitKind, cell: GridCell) -> Option<UnitID> {
+        if let Some(definition) = self.definitions.unit(kind) {
+            let id = UnitID(self.next_unit_id);
+            self.next_unit_id += 1;
+            self.occupancy.reserve(cell, id);
+            self.units.push(Unit::new(id, definition, kind, owner, cell));
+            Some(id)
+
+        } else {
+            None
+        }
+    }
+
+    /// Removes all units and buildings which were destroyed during combat.
+    fn remove_dead(&mut self) {
+
+        let count = self.buildings.len();
+        {
+
+            let (occupancy, players) = (&mut self.occupancy, &mut self.players);
+            self.units.retain(|u| {
+                if u.health == 0 {
+                    println!("[GameState] Unit {:?} of player {:?} died", u.id, u.owner);
+                    if let Some(player) = players.get_mut(u.owner.0 as usize) {
+                        player.stats.units_lost += 1;
+                    }
+                    occupancy.release(u.position(), u.id);
+                    if let Some(next) = u.next() {
+                        occupancy.release(next, u.id);
+                    }
+                    false
+
+                } else {
+                    true
+                }
+            });
+
+            self.buildings.retain(|b| {
+                if b.health == 0 {
+                    if let Some(player) = players.get_mut(b.owner.0 as usize) {
+                        player.stats.buildings_lost += 1;
+                    }
+                    false
+
+                } else {
+                    true
+                }
+            });
+
+        }
+
+        if self.buildings.len() != count {
+            self.update_nav();
+        }
+
+    }
+
     fn consume_tile(&mut self, x: i32, y: i32) {
 
         let effect = if let Some(ref mut terrain) = self.terrain {
             if let Some(terrain) = terrain.consume_tile(x, y) {
                 if terrain.name == "Forest" {
-                    Some(PathBuf::from("../assets/sounds/woodaxe.flac"))
+                    Some("sounds/woodaxe.flac")
 
                 } else if terrain.name == "Rocks" {
-                    Some(PathBuf::from("../assets/sounds/pickaxe.flac"))
+                    Some("sounds/pickaxe.flac")
 
                 } else {
                     None
@@ -123,12 +767,20 @@ impl GameState {
         };
 
         if let Some(effect) = effect {
+
+            // Reflowed terrain may have changed in a wider area
+            self.update_nav();
+            if let Some(ref terrain) = self.terrain {
+                self.fog.update_terrain(terrain.source(), terrain.tileset());
+            }
+
             self.play_effect_at(x, y, effect, true);
+
         }
 
     }
 
-    fn play_effect_at(&mut self, tx: i32, ty: i32, path: PathBuf, vary_speed: bool) {
+    fn play_effect_at(&mut self, tx: i32, ty: i32, path: &str, vary_speed: bool) {
 
         // DE-SYNC: Must always be called
         let speed: Option<f32> = if vary_speed {
@@ -139,9 +791,11 @@ impl GameState {
         };
 
         // Only play effect when it is within the screen bounds
-        if let Some(ref terrain) = self.terrain {
-            if terrain.tile_within_screen_grid(tx, ty, 1) {
-                self.audio.play_effect(path, speed);
+        let visible = self.terrain.as_ref().map(|t| t.tile_within_screen_grid(tx, ty, 1)).unwrap_or(false);
+        if visible {
+            match self.assets.load_sound(path) {
+                Ok(sound) => self.audio.play_sound(self.assets.sound(sound), speed),
+                Err(err) => println!("[GameState] Failed to load sound {:?}: {}", path, err)
             }
         }
 
@@ -157,8 +811,44 @@ impl Default for GameState {
             options: GameOptions::default(),
             rng: XorShiftRng::new_unseeded(),
             audio: AudioQueue::new(),
+            assets: Assets::new(&Assets::find_root()),
+            tilesets: Vec::new(),
+            definitions: Definitions::default(),
+            nav: NavData::default(),
+            occupancy: Occupancy::default(),
+            fog: Fog::default(),
+            players: Vec::new(),
+            ais: Vec::new(),
+            resources: BTreeMap::new(),
+            buildings: Vec::new(),
+            units: Vec::new(),
+            projectiles: Vec::new(),
+            next_building_id: 0,
+            next_unit_id: 0,
+            ticks: 0,
+            outcome: None,
+            triggers: Vec::new(),
+            map: None,
+            map_host: None,
+            download: None,
             terrain: None
         }
     }
 }
 
+
+// Helpers --------------------------------------------------------------------
+/// Start locations placed on the map take precedence over the map corners.
+fn start_locations(objects: &MapObjects, width: i32, height: i32) -> Vec<GridCell> {
+    if !objects.starts.is_empty() {
+        objects.starts.clone()
+
+    } else {
+        vec![
+            GridCell(START_INSET, START_INSET),
+            GridCell(width - START_INSET - 1, height - START_INSET - 1),
+            GridCell(width - START_INSET - 1, START_INSET),
+            GridCell(START_INSET, height - START_INSET - 1)
+        ]
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, NavData};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{Harvest, Occupancy, PlayerID, Target, UnitDefinition, UnitKind};
+
+
+// Statics --------------------------------------------------------------------
+const STEP_TICKS_STRAIGHT: u8 = 3;
+const STEP_TICKS_DIAGONAL: u8 = 4;
+const REPATH_WAIT_TICKS: u8 = 4;
+const REPATH_MAX: u8 = 3;
+const AVOIDANCE_RADIUS: i32 = 3;
+
+
+// Unit Abstraction -----------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct UnitID(pub u16);
+
+#[derive(Debug)]
+pub struct Unit {
+    pub id: UnitID,
+    pub kind: UnitKind,
+    pub owner: PlayerID,
+    pub sight: u8,
+    pub health: u16,
+    pub harvest: Option<Harvest>,
+    pub target: Option<Target>,
+    pub pursue: bool,
+    pub cooldown: u16,
+    position: GridCell,
+    next: Option<GridCell>,
+    goal: Option<GridCell>,
+    path: Vec<GridCell>,
+    step_ticks: u8,
+    step_remaining: u8,
+    wait_ticks: u8,
+    repaths: u8
+}
+
+impl Unit {
+
+    pub fn new(id: UnitID, definition: &UnitDefinition, kind: UnitKind, owner: PlayerID, position: GridCell) -> Self {
+        Self {
+            id: id,
+            kind: kind,
+            owner: owner,
+            sight: definition.sight,
+            health: definition.health,
+            harvest: None,
+            target: None,
+            pursue: false,
+            cooldown: 0,
+            position: position,
+            next: None,
+            goal: None,
+            path: Vec::new(),
+            step_ticks: 0,
+            step_remaining: 0,
+            wait_ticks: 0,
+            repaths: 0
+        }
+    }
+
+    pub fn position(&self) -> GridCell {
+        self.position
+    }
+
+    pub fn next(&self) -> Option<GridCell> {
+        self.next
+    }
+
+    pub fn goal(&self) -> Option<GridCell> {
+        self.goal
+    }
+
+    pub fn path(&self) -> &[GridCell] {
+        &self.path
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.next.is_some()
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.next.is_none() && self.path.is_empty()
+    }
+
+    pub fn world_position(&self, tile_size: f32) -> (f32, f32) {
+        let (x, y) = (self.position.0 as f32 * tile_size, self.position.1 as f32 * tile_size);
+        if let Some(next) = self.next {
+            let p = (self.step_ticks - self.step_remaining) as f32 / self.step_ticks as f32;
+            let (tx, ty) = (next.0 as f32 * tile_size, next.1 as f32 * tile_size);
+            (x + (tx - x) * p, y + (ty - y) * p)
+
+        } else {
+            (x, y)
+        }
+    }
+
+    pub fn move_to(&mut self, nav: &NavData, goal: GridCell) {
+        let from = self.next.unwrap_or(self.position);
+        self.path = nav.find_path(from, goal, |_, _| false).unwrap_or_else(Vec::new);
+        self.goal = Some(goal);
+        self.wait_ticks = 0;
+        self.repaths = 0;
+    }
+
+    pub fn stop(&mut self) {
+        self.path.clear();
+        self.goal = None;
+        self.wait_ticks = 0;
+        self.repaths = 0;
+    }
+
+    pub fn desired_cell(&self) -> Option<GridCell> {
+        if self.next.is_none() {
+            self.path.last().cloned()
+
+        } else {
+            None
+        }
+    }
+
+    pub fn is_final_step(&self) -> bool {
+        self.path.len() == 1
+    }
+
+    // Movement Steps ---------------------------------------------------------
+    pub fn start_step(&mut self, occupancy: &mut Occupancy) {
+        if let Some(cell) = self.path.pop() {
+            occupancy.reserve(cell, self.id);
+            self.begin_step(cell);
+        }
+    }
+
+    /// Starts a step onto a tile which is held by another unit which in turn
+    /// moves onto our current tile.
+    pub fn start_swap(&mut self) {
+        if let Some(cell) = self.path.pop() {
+            self.begin_step(cell);
+        }
+    }
+
+    pub fn advance_step(&mut self, occupancy: &mut Occupancy) {
+        if let Some(next) = self.next {
+            self.step_remaining = self.step_remaining.saturating_sub(1);
+            if self.step_remaining == 0 {
+                occupancy.reserve(next, self.id);
+                occupancy.release(self.position, self.id);
+                self.position = next;
+                self.next = None;
+                if self.path.is_empty() {
+                    self.goal = None;
+                }
+            }
+        }
+    }
+
+    /// Waits in front of a blocked tile and searches for a path around any
+    /// nearby units once the wait exceeds a threshold.
+    pub fn wait(&mut self, nav: &NavData, occupancy: &Occupancy) {
+
+        self.wait_ticks += 1;
+        if self.wait_ticks < REPATH_WAIT_TICKS {
+            return;
+        }
+
+        self.wait_ticks = 0;
+        self.repaths += 1;
+
+        if let Some(goal) = self.goal {
+            if self.repaths <= REPATH_MAX {
+                let (id, p) = (self.id, self.position);
+                self.path = nav.find_path(p, goal, |x, y| {
+                    (x - p.0).abs() <= AVOIDANCE_RADIUS
+                        && (y - p.1).abs() <= AVOIDANCE_RADIUS
+                        && occupancy.get(GridCell(x, y)).map(|o| o != id).unwrap_or(false)
+
+                }).unwrap_or_else(Vec::new);
+
+                if !self.path.is_empty() {
+                    return;
+                }
+            }
+        }
+
+        self.stop();
+
+    }
+
+    fn begin_step(&mut self, cell: GridCell) {
+        self.step_ticks = if cell.0 != self.position.0 && cell.1 != self.position.1 {
+            STEP_TICKS_DIAGONAL
+
+        } else {
+            STEP_TICKS_STRAIGHT
+        };
+        self.step_remaining = self.step_ticks;
+        self.next = Some(cell);
+        self.wait_ticks = 0;
+        self.repaths = 0;
+    }
+
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{Building, Definitions, Player, PlayerID, Unit, Victory};
+
+
+// Match Outcome --------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// Tick on which the match was decided.
+    pub tick: u32,
+    /// Players which won the match, empty in case everyone lost.
+    pub winners: Vec<PlayerID>
+}
+
+
+// Victory Checks -------------------------------------------------------------
+
+/// Marks defeated players and returns the outcome once the match has been
+/// decided by any of the enabled conditions.
+pub fn check(
+    victory: &Victory,
+    tick: u32,
+    players: &mut [Player],
+    units: &[Unit],
+    buildings: &[Building],
+    definitions: &Definitions
+
+) -> Option<Outcome> {
+
+    let headquarters = definitions.building_kind("Headquarters");
+    for player in players.iter_mut().filter(|p| !p.defeated) {
+
+        let id = player.id;
+        let annihilated = !units.iter().any(|u| u.owner == id) && !buildings.iter().any(|b| b.owner == id);
+        let beheaded = !buildings.iter().any(|b| b.owner == id && Some(b.kind) == headquarters);
+
+        if (victory.annihilation && annihilated) || (victory.headquarters && beheaded) {
+            println!("[Victory] Player {:?} was defeated", id);
+            player.defeated = true;
+        }
+
+    }
+
+    let alive: Vec<&Player> = players.iter().filter(|p| !p.defeated).collect();
+
+    // Last one standing, in single player games this only ends in defeat
+    if alive.is_empty() || (players.len() > 1 && alive.len() == 1) {
+        return Some(Outcome {
+            tick: tick,
+            winners: alive.iter().map(|p| p.id).collect()
+        });
+    }
+
+    // Resource race, players reaching the goal on the same tick share the win
+    if victory.resources > 0 {
+        let winners: Vec<PlayerID> = alive.iter().filter(|p| p.stats.gathered >= victory.resources).map(|p| p.id).collect();
+        if !winners.is_empty() {
+            return Some(Outcome {
+                tick: tick,
+                winners: winners
+            });
+        }
+    }
+
+    if victory.time_limit > 0 && tick >= victory.time_limit {
+        let best = alive.iter().map(|p| p.stats.gathered).max().unwrap_or(0);
+        return Some(Outcome {
+            tick: tick,
+            winners: alive.iter().filter(|p| p.stats.gathered == best).map(|p| p.id).collect()
+        });
+    }
+
+    None
+
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::{GridCell, TerrainGrid, TileChange};
+
+
+// Statics --------------------------------------------------------------------
+const MAX_EDITS: usize = 100;
+
+
+// Edits ----------------------------------------------------------------------
+
+/// A single undoable change to the map.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Tiles {
+        undo: Vec<TileChange>,
+        redo: Vec<TileChange>
+    },
+    Starts {
+        before: Vec<GridCell>,
+        after: Vec<GridCell>
+    }
+}
+
+impl Edit {
+
+    fn apply(&self, terrain: &mut TerrainGrid, forward: bool) {
+        match *self {
+            Edit::Tiles { ref undo, ref redo } => {
+                terrain.apply_diff(if forward { redo } else { undo });
+            },
+            Edit::Starts { ref before, ref after } => {
+                terrain.source_mut().objects.starts = if forward {
+                    after.clone()
+
+                } else {
+                    before.clone()
+                };
+            }
+        }
+    }
+
+}
+
+
+// History --------------------------------------------------------------------
+
+/// Undo and redo stacks of the editor.
+///
+/// Pushing a new edit drops all edits which were undone before, only the
+/// most recent `MAX_EDITS` edits are kept.
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>
+}
+
+impl History {
+
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new()
+        }
+    }
+
+    pub fn push(&mut self, edit: Edit) {
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > MAX_EDITS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, terrain: &mut TerrainGrid) -> bool {
+        if let Some(edit) = self.undo.pop() {
+            edit.apply(terrain, false);
+            self.redo.push(edit);
+            true
+
+        } else {
+            false
+        }
+    }
+
+    pub fn redo(&mut self, terrain: &mut TerrainGrid) -> bool {
+        if let Some(edit) = self.redo.pop() {
+            edit.apply(terrain, true);
+            self.undo.push(edit);
+            true
+
+        } else {
+            false
+        }
+    }
+
+}
+
@@ -0,0 +1,476 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::path::{Path, PathBuf};
+
+
+// External Dependencies ------------------------------------------------------
+use renderer::{Key, Keyboard, Button, Mouse, Renderable, RenderTarget, Encoder, Texture};
+use sprites::{Sprite, SpriteSheet, SpriteView};
+use tiles::{GridCell, LayerEncoding, TerrainGrid, TileData, TileSet, TileSource};
+use core::{Assets, Handle, Reloaded};
+
+
+// Modules --------------------------------------------------------------------
+mod history;
+mod palette;
+
+
+// Internal Dependencies ------------------------------------------------------
+use self::history::{Edit, History};
+use self::palette::Palette;
+
+
+// Statics --------------------------------------------------------------------
+const TILE_SIZE: f32 = 32.0;
+const SPRITE_SIZE: u32 = 16;
+const SCROLL_SPEED: i32 = 12;
+const TOOL_KEYS: [Key; 9] = [
+    Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
+    Key::Key6, Key::Key7, Key::Key8, Key::Key9
+];
+
+
+// Tools ----------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+enum Tool {
+    /// Sets single tiles to the palette tile, right click picks the tile
+    /// below the mouse.
+    Tile,
+    /// Replaces the connected area of equal tiles with the palette tile.
+    Fill,
+    /// Toggles player start locations.
+    Start,
+    /// Paints the named terrain, right click consumes it again.
+    Terrain(String)
+}
+
+
+// Editor Implementation ------------------------------------------------------
+
+/// Map editor which modifies and saves a map in place.
+///
+/// Tools are selected with the number keys, `1` is the tile brush, `2` the
+/// fill tool, `3` places start locations and the remaining keys select the
+/// terrain brushes of the tileset in alphabetical order.
+pub struct Editor {
+    path: PathBuf,
+    assets: Assets,
+    tilesets: Vec<Handle<TileSet>>,
+    sprites: Handle<Texture>,
+    terrain: TerrainGrid,
+    palette: Palette,
+    history: History,
+    tools: Vec<Tool>,
+    tool: Tool,
+    tile: u32,
+    show_palette: bool,
+    scroll: (i32, i32),
+    stroke: Option<(TileData, Option<GridCell>)>,
+    sprite_view: SpriteView,
+    ui_view: SpriteView,
+    target: RenderTarget
+}
+
+impl Editor {
+
+    /// `path` is relative to the asset root, saving writes back to it.
+    pub fn new(mut target: RenderTarget, mut assets: Assets, path: &Path) -> Self {
+
+        // Maps are edited in place, so they are never taken from the cache
+        let path = assets.path(path);
+        let m = TileData::new(&path).unwrap_or_else(|err| {
+            panic!("[Editor] Failed to load map {:?}: {}", path, err)
+        });
+
+        // Tilesets are relative to the map
+        let tilesets: Vec<Handle<TileSet>> = m.tilesets.iter().map(|t| {
+            let path = path.parent().unwrap_or(Path::new(".")).join(&t.source);
+            assets.load_tileset(&mut target.factory, &path).unwrap_or_else(|err| {
+                panic!("[Editor] Failed to load tileset {:?}: {}", path, err)
+            })
+
+        }).collect();
+
+        if tilesets.is_empty() {
+            panic!("[Editor] Map {:?} has no tileset.", path);
+        }
+
+        let ts = assets.tileset(tilesets[0]).clone();
+        let layer_tilesets = tilesets[1..].iter().map(|h| assets.tileset(*h).clone()).collect();
+
+        let palette = Palette::new(&mut target, &ts);
+
+        let mut tools = vec![Tool::Tile, Tool::Fill, Tool::Start];
+        for name in ts.terrain_names() {
+            if tools.len() < TOOL_KEYS.len() {
+                tools.push(Tool::Terrain(name.to_string()));
+            }
+        }
+
+        let mut terrain = TerrainGrid::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            TILE_SIZE as u32,
+            ts
+
+        ).with_layers(
+            &mut target.factory,
+            target.color.clone(),
+            &m.layers,
+            layer_tilesets
+        );
+
+        terrain.set_source(m);
+
+        let sprites = assets.load_texture(
+            &mut target.factory,
+            "textures/debug.png"
+
+        ).expect("[Editor] Failed to load sprites.");
+
+        let sprite_view = SpriteView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            SpriteSheet::from_texture(assets.texture(sprites).clone(), SPRITE_SIZE)
+        );
+
+        let ui_view = SpriteView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            SpriteSheet::from_texture(assets.texture(sprites).clone(), SPRITE_SIZE)
+        );
+
+        Self {
+            path: path,
+            assets: assets,
+            tilesets: tilesets,
+            sprites: sprites,
+            terrain: terrain,
+            palette: palette,
+            history: History::new(),
+            tools: tools,
+            tool: Tool::Tile,
+            tile: 0,
+            show_palette: false,
+            scroll: (0, 0),
+            stroke: None,
+            sprite_view: sprite_view,
+            ui_view: ui_view,
+            target: target
+        }
+
+    }
+
+    /// Applies the current tool to a single cell, right clicks use the
+    /// tool's secondary action.
+    fn apply_tool(&mut self, x: i32, y: i32, secondary: bool) {
+        match self.tool {
+            Tool::Tile => if secondary {
+                if let Some(index) = self.terrain.get_tile_index(x, y) {
+                    self.tile = index;
+                }
+
+            } else {
+                self.terrain.set_tile_index(x, y, self.tile);
+            },
+            Tool::Fill => if !secondary {
+                flood_fill(&mut self.terrain, x, y, self.tile);
+            },
+            Tool::Start => {
+                let starts = &mut self.terrain.source_mut().objects.starts;
+                let cell = GridCell(x, y);
+                if starts.contains(&cell) {
+                    starts.retain(|c| *c != cell);
+
+                } else if !secondary {
+                    starts.push(cell);
+                }
+            },
+            Tool::Terrain(ref name) => {
+                self.terrain.set_terrain(name, x, y, !secondary);
+            }
+        }
+    }
+
+    /// Turns the changes since the start of the current stroke into a
+    /// single undoable edit.
+    fn finish_stroke(&mut self) {
+        if let Some((before, _)) = self.stroke.take() {
+            let after = self.terrain.source();
+            if before.objects.starts != after.objects.starts {
+                self.history.push(Edit::Starts {
+                    before: before.objects.starts.clone(),
+                    after: after.objects.starts.clone()
+                });
+
+            } else {
+                let undo = before.diff(after);
+                if !undo.is_empty() {
+                    self.history.push(Edit::Tiles {
+                        undo: undo,
+                        redo: after.diff(&before)
+                    });
+                }
+            }
+        }
+    }
+
+    /// Swaps in textures and tilesets which changed on disk, the palette is
+    /// rebuilt as the tileset may have grown.
+    fn reload_assets(&mut self) {
+        for reloaded in self.assets.reload(&mut self.target.factory) {
+            match reloaded {
+                Reloaded::Texture(handle) => if handle == self.sprites {
+                    let texture = self.assets.texture(handle);
+                    self.sprite_view.set_sheet(SpriteSheet::from_texture(texture.clone(), SPRITE_SIZE));
+                    self.ui_view.set_sheet(SpriteSheet::from_texture(texture.clone(), SPRITE_SIZE));
+                },
+                Reloaded::TileSet(handle) => {
+                    for (i, h) in self.tilesets.iter().enumerate() {
+                        if *h == handle {
+                            self.terrain.set_tileset(i, self.assets.tileset(handle).clone());
+                        }
+                    }
+                    if self.tilesets[0] == handle {
+                        self.palette = Palette::new(&mut self.target, self.assets.tileset(handle));
+                    }
+                }
+            }
+        }
+    }
+
+    fn save(&self) {
+        match self.terrain.source().save(&self.path, LayerEncoding::Csv) {
+            Ok(()) => println!("[Editor] Saved map to {:?}", self.path),
+            Err(err) => println!("[Editor] Failed to save map to {:?}: {}", self.path, err)
+        }
+    }
+
+}
+
+impl Renderable for Editor {
+
+    fn tick(&mut self, _: u64) where Self: Sized {}
+
+    fn draw(
+        &mut self,
+        time: u64,
+        _: f32,
+        mut encoder: &mut Encoder,
+        keyboard: &Keyboard,
+        mouse: &Mouse
+
+    ) where Self: Sized {
+
+        self.reload_assets();
+
+        let control = keyboard.is_pressed(Key::LControl) || keyboard.is_pressed(Key::RControl);
+        if control {
+            if keyboard.was_pressed(Key::Z) {
+                self.history.undo(&mut self.terrain);
+
+            } else if keyboard.was_pressed(Key::Y) {
+                self.history.redo(&mut self.terrain);
+
+            } else if keyboard.was_pressed(Key::S) {
+                self.save();
+            }
+
+        // Scrolling
+        } else {
+            if keyboard.is_pressed(Key::A) {
+                self.scroll.0 -= SCROLL_SPEED;
+            }
+
+            if keyboard.is_pressed(Key::D) {
+                self.scroll.0 += SCROLL_SPEED;
+            }
+
+            if keyboard.is_pressed(Key::W) {
+                self.scroll.1 -= SCROLL_SPEED;
+            }
+
+            if keyboard.is_pressed(Key::S) {
+                self.scroll.1 += SCROLL_SPEED;
+            }
+        }
+
+        // Tools
+        for (i, key) in TOOL_KEYS.iter().enumerate() {
+            if keyboard.was_pressed(*key) {
+                if let Some(tool) = self.tools.get(i).cloned() {
+                    println!("[Editor] Selected tool {:?}", tool);
+                    self.tool = tool;
+                }
+            }
+        }
+
+        if keyboard.was_pressed(Key::T) {
+            self.show_palette = !self.show_palette;
+        }
+
+        self.scroll = self.terrain.scroll_to(self.scroll.0, self.scroll.1);
+
+        // Picking a tile from the palette also switches to the tile brush
+        let (mx, my) = mouse.position();
+        let picked = if self.show_palette && self.stroke.is_none() {
+            self.palette.tile_at(mx, my)
+
+        } else {
+            None
+        };
+
+        if let Some(tile) = picked {
+            if mouse.was_pressed(Button::Left) {
+                self.tile = tile;
+                if self.tool != Tool::Fill {
+                    self.tool = Tool::Tile;
+                }
+            }
+
+        } else {
+
+            let cell = self.terrain.screen_to_grid(mx, my);
+            let cell = GridCell(cell.0, cell.1);
+
+            if self.stroke.is_none() && (mouse.was_pressed(Button::Left) || mouse.was_pressed(Button::Right)) {
+                self.stroke = Some((self.terrain.source().clone(), None));
+            }
+
+            // Each cell is only edited once per stroke, start locations
+            // are toggled by single clicks
+            let apply = if let Some((_, ref mut last)) = self.stroke {
+                if *last != Some(cell) && (self.tool != Tool::Start || last.is_none()) {
+                    *last = Some(cell);
+                    true
+
+                } else {
+                    false
+                }
+
+            } else {
+                false
+            };
+
+            if apply {
+                let secondary = !mouse.is_pressed(Button::Left);
+                self.apply_tool(cell.0, cell.1, secondary);
+            }
+
+        }
+
+        if !mouse.is_pressed(Button::Left) && !mouse.is_pressed(Button::Right) {
+            self.finish_stroke();
+        }
+
+        self.terrain.animate(time);
+        self.terrain.draw(&mut encoder);
+
+        // Start locations and the hovered cell
+        self.sprite_view.scroll_to(self.scroll.0, self.scroll.1);
+
+        let mut sprite = Sprite::new();
+        sprite.set_size(TILE_SIZE, TILE_SIZE);
+        sprite.set_tile(3);
+        for cell in &self.terrain.source().objects.starts {
+            sprite.set_position(cell.0 as f32 * TILE_SIZE, cell.1 as f32 * TILE_SIZE);
+            self.sprite_view.draw_sprite(&sprite);
+        }
+
+        if picked.is_none() {
+            let (x, y) = self.terrain.screen_to_grid(mx, my);
+            sprite.set_position(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+            sprite.set_tile(9);
+            self.sprite_view.draw_sprite(&sprite);
+        }
+
+        self.sprite_view.draw(&mut self.target.factory, encoder);
+
+        // Palette with the current tile highlighted
+        if self.show_palette {
+            self.palette.draw(encoder);
+
+            let (x, y, size) = self.palette.tile_rect(self.tile);
+            sprite.set_size(size + 4.0, size + 4.0);
+            sprite.set_position(x - 2.0, y - 2.0);
+            sprite.set_tile(9);
+            self.ui_view.draw_sprite(&sprite);
+            self.ui_view.draw(&mut self.target.factory, encoder);
+        }
+
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+
+/// Fills the connected area of equal tiles.
+///
+/// Terrain tiles are filled with the center tile of their group, the edges
+/// of the area are then reflowed so they border correctly onto the
+/// surrounding tiles.
+fn flood_fill(terrain: &mut TerrainGrid, x: i32, y: i32, tile: u32) {
+
+    let original = match terrain.get_tile_index(x, y) {
+        Some(index) => index,
+        None => return
+    };
+
+    let (fill, names) = {
+        let tileset = terrain.tileset();
+        let mut names: Vec<String> = [tile, original].iter().filter_map(|t| {
+            tileset.get_tile_terrain(*t).map(|t| t.name.to_string())
+
+        }).collect();
+        names.dedup();
+        (tileset.get_tile_terrain(tile).map(|t| t.group_tile()).unwrap_or(tile), names)
+    };
+
+    if fill == original {
+        return;
+    }
+
+    let mut filled = Vec::new();
+    let mut open = vec![(x, y)];
+    while let Some((x, y)) = open.pop() {
+        if terrain.get_tile_index(x, y) == Some(original) {
+            terrain.set_tile_index(x, y, fill);
+            filled.push((x, y));
+            open.push((x - 1, y));
+            open.push((x + 1, y));
+            open.push((x, y - 1));
+            open.push((x, y + 1));
+        }
+    }
+
+    // Only tiles next to other tiles can be part of an edge
+    let edges: Vec<(i32, i32)> = filled.into_iter().filter(|&(x, y)| {
+        (-1..2).any(|dx| (-1..2).any(|dy| {
+            terrain.get_tile_index(x + dx, y + dy).map(|i| i != fill).unwrap_or(false)
+        }))
+
+    }).collect();
+
+    for name in &names {
+        for &(x, y) in &edges {
+            terrain.reflow(name, x, y);
+        }
+    }
+
+}
+
@@ -0,0 +1,123 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use renderer::{Encoder, QuadView, RenderTarget, Vertex};
+use tiles::TileSet;
+
+
+// Statics --------------------------------------------------------------------
+const PALETTE_TILE_SIZE: f32 = 24.0;
+const PALETTE_MARGIN: f32 = 8.0;
+
+
+// Palette --------------------------------------------------------------------
+
+/// All tiles of a tileset laid out in their original rows and columns.
+pub struct Palette {
+    view: QuadView,
+    size: (u32, u32),
+    origin: (f32, f32)
+}
+
+impl Palette {
+
+    pub fn new(target: &mut RenderTarget, tileset: &TileSet) -> Self {
+
+        let size = tileset.size();
+        let origin = (PALETTE_MARGIN, PALETTE_MARGIN);
+        let view = (target.width, target.height);
+
+        let mut vertices = Vec::with_capacity((size.0 * size.1 * 6) as usize);
+        for index in 0..size.0 * size.1 {
+            let (x, y) = (
+                origin.0 + (index % size.0) as f32 * PALETTE_TILE_SIZE,
+                origin.1 + (index / size.0) as f32 * PALETTE_TILE_SIZE
+            );
+            vertices.extend(create_quad(view, (x, y), tileset.uvs(index)));
+        }
+
+        let quad_view = QuadView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            tileset.texture().bind(),
+            vertices
+        );
+
+        Self {
+            view: quad_view,
+            size: size,
+            origin: origin
+        }
+
+    }
+
+    /// Returns the tile below the given screen position.
+    pub fn tile_at(&self, x: i32, y: i32) -> Option<u32> {
+        let (x, y) = (
+            (x as f32 - self.origin.0) / PALETTE_TILE_SIZE,
+            (y as f32 - self.origin.1) / PALETTE_TILE_SIZE
+        );
+        if x >= 0.0 && y >= 0.0 && (x as u32) < self.size.0 && (y as u32) < self.size.1 {
+            Some(x as u32 + y as u32 * self.size.0)
+
+        } else {
+            None
+        }
+    }
+
+    /// Returns the screen position and size of a tile.
+    pub fn tile_rect(&self, tile: u32) -> (f32, f32, f32) {
+        (
+            self.origin.0 + (tile % self.size.0) as f32 * PALETTE_TILE_SIZE,
+            self.origin.1 + (tile / self.size.0) as f32 * PALETTE_TILE_SIZE,
+            PALETTE_TILE_SIZE
+        )
+    }
+
+    pub fn draw(&mut self, encoder: &mut Encoder) {
+        self.view.draw(encoder, None);
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn create_quad(view: (u32, u32), position: (f32, f32), uvs: [[f32; 2]; 4]) -> Vec<Vertex> {
+
+    let s = PALETTE_TILE_SIZE;
+    let (x, y) = (
+        position.0 - view.0 as f32 / 2.0,
+        -(position.1 - view.1 as f32 / 2.0 + s)
+    );
+
+    vec![
+        // Top left
+        Vertex { pos: [x, y], uv: uvs[0], color: [1.0; 4] },
+
+        // Top right
+        Vertex { pos: [x + s, y], uv: uvs[1], color: [1.0; 4] },
+
+        // Bottom left
+        Vertex { pos: [x, y + s], uv: uvs[2], color: [1.0; 4] },
+
+        // Top right
+        Vertex { pos: [x + s, y], uv: uvs[1], color: [1.0; 4] },
+
+        // Bottom right
+        Vertex { pos: [x + s, y + s], uv: uvs[3], color: [1.0; 4] },
+
+        // Bottom left
+        Vertex { pos: [x, y + s], uv: uvs[2], color: [1.0; 4] }
+    ]
+
+}
+
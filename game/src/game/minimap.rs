@@ -0,0 +1,273 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use renderer::{Button, Encoder, Factory, Mouse, QuadView, RenderTarget, Texture, Vertex};
+use sprites::{Sprite, SpriteSheet, SpriteView};
+use tiles::{GridCell, OverlayShade, TerrainGrid, TileSource, TileType};
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{GameState, PlayerID};
+
+
+// Statics --------------------------------------------------------------------
+const MINIMAP_SIZE: f32 = 160.0;
+const MINIMAP_MARGIN: f32 = 8.0;
+const MIN_DOT_SIZE: f32 = 2.0;
+const OWNER_TILES: [u32; 4] = [4, 2, 3, 5];
+
+
+// Minimap --------------------------------------------------------------------
+
+/// A downscaled view of the whole map in the top right corner of the screen.
+///
+/// Every map tile is a single texel, after the initial upload only the tiles
+/// which were changed by consuming terrain are updated.
+pub struct Minimap {
+    terrain: Texture,
+    fog: Texture,
+    terrain_view: QuadView,
+    fog_view: QuadView,
+    sprite_view: SpriteView,
+    map_size: (u32, u32),
+    origin: (f32, f32),
+    scale: f32,
+    uploaded: bool,
+    dragging: bool
+}
+
+impl Minimap {
+
+    pub fn new(target: &mut RenderTarget, map_size: (u32, u32), sheet: SpriteSheet) -> Self {
+
+        let scale = (MINIMAP_SIZE / map_size.0 as f32).min(MINIMAP_SIZE / map_size.1 as f32);
+        let (w, h) = (map_size.0 as f32 * scale, map_size.1 as f32 * scale);
+        let origin = (target.width as f32 - w - MINIMAP_MARGIN, MINIMAP_MARGIN);
+
+        let terrain = Texture::dynamic(&mut target.factory, map_size).expect("[Minimap] Failed to create terrain texture.");
+        let fog = Texture::dynamic(&mut target.factory, map_size).expect("[Minimap] Failed to create fog texture.");
+
+        let view = (target.width, target.height);
+        let terrain_view = QuadView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            terrain.bind(),
+            create_quad(view, origin, (w, h))
+        );
+
+        let fog_view = QuadView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            fog.bind(),
+            create_quad(view, origin, (w, h))
+        );
+
+        let sprite_view = SpriteView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            sheet
+        );
+
+        Self {
+            terrain: terrain,
+            fog: fog,
+            terrain_view: terrain_view,
+            fog_view: fog_view,
+            sprite_view: sprite_view,
+            map_size: map_size,
+            origin: origin,
+            scale: scale,
+            uploaded: false,
+            dragging: false
+        }
+
+    }
+
+    pub fn set_sheet(&mut self, sheet: SpriteSheet) {
+        self.sprite_view.set_sheet(sheet);
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Returns the map tile below the mouse while the left button is held
+    /// down after being pressed on the minimap.
+    pub fn drag(&mut self, mouse: &Mouse) -> Option<(i32, i32)> {
+
+        let (x, y) = mouse.position();
+        if mouse.was_pressed(Button::Left) {
+            self.dragging = self.contains(x, y);
+
+        } else if !mouse.is_pressed(Button::Left) {
+            self.dragging = false;
+        }
+
+        if self.dragging {
+            let (w, h) = (self.map_size.0 as i32, self.map_size.1 as i32);
+            Some((
+                (((x as f32 - self.origin.0) / self.scale) as i32).max(0).min(w - 1),
+                (((y as f32 - self.origin.1) / self.scale) as i32).max(0).min(h - 1)
+            ))
+
+        } else {
+            None
+        }
+
+    }
+
+    /// Uploads all tiles on the first call and afterwards only those which
+    /// changed in the meantime.
+    pub fn update_terrain(&mut self, encoder: &mut Encoder, terrain: &mut TerrainGrid) {
+
+        // Tiles are only tracked once the whole terrain has been uploaded
+        let changed = terrain.source_mut().take_changed();
+        terrain.source_mut().set_track_changes(true);
+
+        let source = terrain.source();
+        let tileset = terrain.tileset();
+
+        if !self.uploaded {
+            self.uploaded = true;
+            let pixels: Vec<[u8; 4]> = source.indices().iter().map(|i| tile_color(tileset.typ(*i))).collect();
+            self.terrain.update(encoder, (0, 0), self.map_size, &pixels);
+
+        } else {
+            for (x, y) in changed {
+                if let Some(index) = source.get_tile_index(x, y) {
+                    self.terrain.update(encoder, (x as u32, y as u32), (1, 1), &[tile_color(tileset.typ(index))]);
+                }
+            }
+        }
+
+    }
+
+    /// Uploads the shades of all tiles, callers only pass them in after the
+    /// fog changed.
+    pub fn set_fog(&mut self, encoder: &mut Encoder, shades: &[OverlayShade]) {
+        let pixels: Vec<[u8; 4]> = shades.iter().map(|s| fog_color(*s)).collect();
+        if pixels.len() == (self.map_size.0 * self.map_size.1) as usize {
+            self.fog.update(encoder, (0, 0), self.map_size, &pixels);
+        }
+    }
+
+    /// Draws the map with dots for all visible units and the outline of the
+    /// main view spanning the cells from `view.0` to `view.1`.
+    pub fn draw(
+        &mut self,
+        factory: &mut Factory,
+        encoder: &mut Encoder,
+        state: &GameState,
+        local: Option<PlayerID>,
+        view: ((i32, i32), (i32, i32))
+    ) {
+
+        self.terrain_view.draw(encoder, None);
+        self.fog_view.draw(encoder, None);
+
+        let fog = state.fog();
+        let size = self.scale.max(MIN_DOT_SIZE);
+
+        let mut dot = Sprite::new();
+        dot.set_size(size, size);
+
+        for unit in state.units() {
+            let p = unit.position();
+            if Some(unit.owner) == local || local.map(|l| fog.is_visible(l, p)).unwrap_or(false) {
+                let (x, y) = self.cell_to_screen(p);
+                dot.set_position(x, y);
+                dot.set_tile(OWNER_TILES[unit.owner.0 as usize % OWNER_TILES.len()]);
+                self.sprite_view.draw_sprite(&dot);
+            }
+        }
+
+        let ((x0, y0), (x1, y1)) = view;
+        let (x, y) = self.cell_to_screen(GridCell(x0, y0));
+        let mut outline = Sprite::new();
+        outline.set_size((x1 - x0 + 1) as f32 * self.scale, (y1 - y0 + 1) as f32 * self.scale);
+        outline.set_position(x, y);
+        outline.set_tile(9);
+        outline.set_layer(1);  // Above all unit dots
+        self.sprite_view.draw_sprite(&outline);
+
+        self.sprite_view.draw(factory, encoder);
+
+    }
+
+    fn contains(&self, x: i32, y: i32) -> bool {
+        let (w, h) = (self.map_size.0 as f32 * self.scale, self.map_size.1 as f32 * self.scale);
+        let (x, y) = (x as f32, y as f32);
+        x >= self.origin.0 && x < self.origin.0 + w && y >= self.origin.1 && y < self.origin.1 + h
+    }
+
+    fn cell_to_screen(&self, cell: GridCell) -> (f32, f32) {
+        (
+            self.origin.0 + cell.0 as f32 * self.scale,
+            self.origin.1 + cell.1 as f32 * self.scale
+        )
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn tile_color(typ: TileType) -> [u8; 4] {
+    match typ {
+        TileType::Ground => [104, 136, 64, 255],
+        TileType::Water => [48, 88, 160, 255],
+        TileType::Other => [64, 72, 56, 255]
+    }
+}
+
+fn fog_color(shade: OverlayShade) -> [u8; 4] {
+    match shade {
+        OverlayShade::Clear => [0, 0, 0, 0],
+        OverlayShade::Dim => [0, 0, 0, 128],
+        OverlayShade::Dark => [0, 0, 0, 255]
+    }
+}
+
+/// Creates the vertices of a single textured quad, positioned in screen
+/// pixels.
+fn create_quad(view: (u32, u32), position: (f32, f32), size: (f32, f32)) -> Vec<Vertex> {
+
+    let (w, h) = size;
+    let (x, y) = (
+        position.0 - view.0 as f32 / 2.0,
+        -(position.1 - view.1 as f32 / 2.0 + h)
+    );
+
+    vec![
+        // Top left
+        Vertex { pos: [x, y], uv: [0.0, 1.0], color: [1.0; 4] },
+
+        // Top right
+        Vertex { pos: [x + w, y], uv: [1.0, 1.0], color: [1.0; 4] },
+
+        // Bottom left
+        Vertex { pos: [x, y + h], uv: [0.0, 0.0], color: [1.0; 4] },
+
+        // Top right
+        Vertex { pos: [x + w, y], uv: [1.0, 1.0], color: [1.0; 4] },
+
+        // Bottom right
+        Vertex { pos: [x + w, y + h], uv: [1.0, 0.0], color: [1.0; 4] },
+
+        // Bottom left
+        Vertex { pos: [x, y + h], uv: [0.0, 0.0], color: [1.0; 4] }
+    ]
+
+}
@@ -7,13 +7,53 @@
 // except according to those terms.
 
 
+// STD Dependencies -----------------------------------------------------------
+use std::mem;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+
 // External Dependencies ------------------------------------------------------
-use renderer::{Key, Keyboard, Button, Mouse, Renderable, RenderTarget, Encoder};
-use clockwork::{Clockwork, Event};
+use renderer::{Key, Keyboard, Button, Mouse, Renderable, RenderTarget, Encoder, Texture};
+use sprites::{Sprite, SpriteSheet, SpriteView};
+use tiles::GridCell;
+use clockwork::{Clockwork, ConnectionID, Event};
+
+
+// Modules --------------------------------------------------------------------
+mod minimap;
+mod selection;
 
 
 // Internal Dependencies ------------------------------------------------------
-pub use core::{GameInput, GameOptions, GameState};
+use self::minimap::Minimap;
+use self::selection::Selection;
+pub use core::{BuildingID, BuildingKind, DownloadStatus, GameInput, GameOptions, GameState, Handle, Reloaded, Target, UnitID};
+
+
+// Statics --------------------------------------------------------------------
+const TILE_SIZE: f32 = 32.0;
+const SPRITE_SIZE: u32 = 16;
+const HEALTH_BAR_HEIGHT: f32 = 4.0;
+const PROJECTILE_SIZE: f32 = 8.0;
+const RESULT_ROW_HEIGHT: f32 = 40.0;
+const RESULT_BAR_WIDTH: f32 = 160.0;
+const DOWNLOAD_BAR_WIDTH: f32 = 320.0;
+
+// Bounds for the time between two state advances, in milliseconds
+const MIN_ADVANCE_INTERVAL: u64 = 16;
+const MAX_ADVANCE_INTERVAL: u64 = 500;
+
+// Sprite layers, from the ground up
+const LAYER_DECALS: i32 = 0;
+const LAYER_SELECTION: i32 = 1;
+const LAYER_UNITS: i32 = 2;
+const LAYER_PROJECTILES: i32 = 3;
+const LAYER_UI: i32 = 4;
+const GROUP_KEYS: [Key; 10] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9
+];
 
 
 // Game Implementation --------------------------------------------------------
@@ -21,23 +61,207 @@ pub struct Game {
     client: Clockwork<GameState, GameOptions, GameInput, RenderTarget>,
     options: GameOptions,
     scroll: (i32, i32),
+    local_id: Option<ConnectionID>,
+    selection: Selection,
+    placing: Option<BuildingKind>,
+    finished: bool,
+    download_failed: bool,
+    positions: HashMap<UnitID, ((f32, f32), (f32, f32))>,
+    last_ticks: u32,
+    advanced_at: Instant,
+    advance_interval: u64,
+    minimap: Option<Minimap>,
+    fog_generation: Option<u32>,
+    sprites: Handle<Texture>,
+    sprite_view: SpriteView,
     target: RenderTarget
 }
 
 impl Game {
     pub fn new(
-        client: Clockwork<GameState, GameOptions, GameInput, RenderTarget>,
+        mut client: Clockwork<GameState, GameOptions, GameInput, RenderTarget>,
         options: GameOptions,
-        target: RenderTarget
+        mut target: RenderTarget
 
     ) -> Game {
+
+        let sprites = client.state().assets().load_texture(
+            &mut target.factory,
+            "textures/debug.png"
+
+        ).expect("[Game] Failed to load unit sprites.");
+
+        let sheet = SpriteSheet::from_texture(
+            client.state().assets().texture(sprites).clone(),
+            SPRITE_SIZE
+        );
+
+        let sprite_view = SpriteView::new(
+            &mut target.factory,
+            target.color.clone(),
+            target.width,
+            target.height,
+            sheet
+        );
+
         Self {
             client: client,
             options: options,
             scroll: (0, 0),
+            local_id: None,
+            selection: Selection::new(),
+            placing: None,
+            finished: false,
+            download_failed: false,
+            positions: HashMap::new(),
+            last_ticks: 0,
+            advanced_at: Instant::now(),
+            advance_interval: 100,
+            minimap: None,
+            fog_generation: None,
+            sprites: sprites,
+            sprite_view: sprite_view,
             target: target
         }
+
+    }
+
+    /// Replaces the sprite sheets of all views after the sprite texture was
+    /// reloaded.
+    fn reload_sprites(&mut self) {
+        let texture = self.client.state().assets().texture(self.sprites).clone();
+        if let Some(ref mut minimap) = self.minimap {
+            minimap.set_sheet(SpriteSheet::from_texture(texture.clone(), SPRITE_SIZE));
+        }
+        self.sprite_view.set_sheet(SpriteSheet::from_texture(texture, SPRITE_SIZE));
+    }
+
+    /// Keeps the previous and current world positions of all units so they
+    /// can be interpolated between simulation ticks.
+    ///
+    /// Units move from where they are currently drawn towards their new
+    /// positions over the time between the last two state advances, so
+    /// several ticks which run at once are spread evenly across it.
+    fn update_positions(&mut self) {
+
+        let now = Instant::now();
+        let alpha = self.interpolation(now);
+        let ticks = self.client.state().ticks();
+        let advanced = ticks != self.last_ticks;
+        if advanced {
+            let interval = duration_ms(now.duration_since(self.advanced_at));
+            self.advance_interval = interval.max(MIN_ADVANCE_INTERVAL).min(MAX_ADVANCE_INTERVAL);
+            self.advanced_at = now;
+            self.last_ticks = ticks;
+        }
+
+        let previous = mem::replace(&mut self.positions, HashMap::new());
+        for unit in self.client.state().units() {
+            let current = unit.world_position(TILE_SIZE);
+            let from = previous.get(&unit.id).map(|&(from, to)| {
+                if advanced {
+                    lerp(from, to, alpha)
+
+                } else {
+                    from
+                }
+
+            }).unwrap_or(current);
+            self.positions.insert(unit.id, (from, current));
+        }
+
+    }
+
+    /// Progress from the previous towards the current unit positions.
+    ///
+    /// The renderer's alpha only covers its own tick, the state however
+    /// advances whenever a sequence arrives, which may take several of them.
+    fn interpolation(&self, now: Instant) -> f32 {
+        let elapsed = duration_ms(now.duration_since(self.advanced_at));
+        (elapsed as f32 / self.advance_interval as f32).min(1.0)
+    }
+
+    /// Draws the progress of downloading the map from the host, a failed
+    /// download is shown as a full red bar.
+    fn draw_download(&mut self, encoder: &mut Encoder, progress: f32, failed: bool) {
+
+        let (x, y) = (
+            (self.target.width as f32 - DOWNLOAD_BAR_WIDTH) / 2.0,
+            (self.target.height as f32 - TILE_SIZE) / 2.0
+        );
+
+        self.sprite_view.scroll_to(0, 0);
+
+        let mut sprite = Sprite::new();
+        sprite.set_size(DOWNLOAD_BAR_WIDTH + 4.0, TILE_SIZE + 4.0);
+        sprite.set_position(x - 2.0, y - 2.0);
+        sprite.set_tile(9);
+        sprite.set_layer(LAYER_SELECTION);
+        self.sprite_view.draw_sprite(&sprite);
+
+        sprite.set_size((DOWNLOAD_BAR_WIDTH * progress).max(1.0), TILE_SIZE);
+        sprite.set_position(x, y);
+        sprite.set_tile(if failed { 2 } else { 4 });
+        sprite.set_layer(LAYER_UI);
+        self.sprite_view.draw_sprite(&sprite);
+
+        self.sprite_view.draw(&mut self.target.factory, encoder);
+
+    }
+
+    /// Draws one row per player, winners are marked green and defeated
+    /// players red, followed by bars for the resources they gathered and the
+    /// units they produced and lost.
+    fn draw_results(&mut self, encoder: &mut Encoder) {
+
+        let state = self.client.state();
+        let local = self.local_id.and_then(|id| state.player_id(id));
+        let winners = state.outcome().map(|o| o.winners.clone()).unwrap_or_else(Vec::new);
+
+        let players = state.players();
+        let max_gathered = players.iter().map(|p| p.stats.gathered).max().unwrap_or(0).max(1);
+        let max_units = players.iter().map(|p| p.stats.units_produced.max(p.stats.units_lost)).max().unwrap_or(0).max(1);
+
+        self.sprite_view.scroll_to(0, 0);
+
+        let mut sprite = Sprite::new();
+        for (i, player) in players.iter().enumerate() {
+
+            let y = TILE_SIZE + i as f32 * RESULT_ROW_HEIGHT;
+            if Some(player.id) == local {
+                sprite.set_size(TILE_SIZE + 4.0, TILE_SIZE + 4.0);
+                sprite.set_position(TILE_SIZE - 2.0, y - 2.0);
+                sprite.set_tile(9);
+                sprite.set_layer(LAYER_SELECTION);
+                self.sprite_view.draw_sprite(&sprite);
+            }
+
+            sprite.set_size(TILE_SIZE, TILE_SIZE);
+            sprite.set_position(TILE_SIZE, y);
+            sprite.set_tile(if winners.contains(&player.id) { 3 } else { 2 });
+            sprite.set_layer(LAYER_UI);
+            self.sprite_view.draw_sprite(&sprite);
+
+            let bars = [
+                (player.stats.gathered as f32 / max_gathered as f32, 1),
+                (player.stats.units_produced as f32 / max_units as f32, 4),
+                (player.stats.units_lost as f32 / max_units as f32, 5)
+            ];
+
+            let height = TILE_SIZE / bars.len() as f32;
+            for (j, &(value, tile)) in bars.iter().enumerate() {
+                sprite.set_size((RESULT_BAR_WIDTH * value).max(1.0), height - 2.0);
+                sprite.set_position(TILE_SIZE * 2.0 + 8.0, y + j as f32 * height);
+                sprite.set_tile(tile);
+                self.sprite_view.draw_sprite(&sprite);
+            }
+
+        }
+
+        self.sprite_view.draw(&mut self.target.factory, encoder);
+
     }
+
 }
 
 impl Renderable for Game {
@@ -48,6 +272,7 @@ impl Renderable for Game {
             match event {
                 Event::HostConnect(address, host_id, local_id) => {
                     println!("[Network] Connected to host {:?}({:?}) as {:?}, but not yet ready...", address, host_id, local_id);
+                    self.local_id = Some(local_id);
                     self.client.set_options(self.options.clone());
                 },
                 Event::HostReady(address, host_id, local_id) => {
@@ -65,6 +290,11 @@ impl Renderable for Game {
                 Event::RemoteLeft(address, id) => {
                     println!("[Network] Remote {:?}{:?} left", address, id);
                 },
+                Event::GameOver => {
+                    println!("[Network] Game over");
+                    self.finished = true;
+                    print_results(self.client.state());
+                },
                 Event::Error(err) => {
                     println!("[Network] Error: {:?}", err);
                     self.client.disconnect();
@@ -73,19 +303,58 @@ impl Renderable for Game {
             }
         }
 
+        // Without the map the game can never start
+        if !self.download_failed {
+            let status = self.client.state().download_status();
+            if let Some(DownloadStatus::Failed(err)) = status {
+                println!("[Game] Failed to download the map from the host: {}", err);
+                self.download_failed = true;
+                self.client.disconnect();
+                return;
+            }
+        }
+
+        self.update_positions();
         self.client.send();
 
     }
 
     fn draw(
         &mut self,
-        _: u64,
+        time: u64,
+        _: f32,
         mut encoder: &mut Encoder,
         keyboard: &Keyboard,
         mouse: &Mouse
 
     ) where Self: Sized {
 
+        // Textures and tilesets which changed on disk are swapped in place
+        for reloaded in self.client.state().reload_assets(&mut self.target.factory) {
+            if reloaded == Reloaded::Texture(self.sprites) {
+                self.reload_sprites();
+            }
+        }
+
+        if self.finished {
+            self.draw_results(encoder);
+            return;
+        }
+
+        let status = self.client.state().download_status();
+        match status {
+            Some(DownloadStatus::Pending(received, total)) => {
+                let progress = if total > 0 { received as f32 / total as f32 } else { 0.0 };
+                self.draw_download(encoder, progress, false);
+                return;
+            },
+            Some(DownloadStatus::Failed(_)) => {
+                self.draw_download(encoder, 1.0, true);
+                return;
+            },
+            _ => {}
+        }
+
         // Scrolling
         if keyboard.is_pressed(Key::A) {
             self.scroll.0 -= 12;
@@ -103,28 +372,323 @@ impl Renderable for Game {
             self.scroll.1 += 12;
         }
 
-        // Map
-        let input = if let Some(ref mut terrain) = self.client.state().terrain {
+        // Unit positions are interpolated across state advances
+        let alpha = self.interpolation(Instant::now());
+
+        let mut inputs = Vec::new();
+        {
+
+            // Fog of war for the local player, only rebuilt when it changed
+            let local_id = self.local_id;
+            let state = self.client.state();
+            let local = local_id.and_then(|id| state.player_id(id));
+            let generation = local.and_then(|p| state.fog().generation(p));
+            let shades = match (local, generation) {
+                (Some(p), Some(g)) if generation != self.fog_generation => Some((g, state.fog().shades(p))),
+                _ => None
+            };
+
+            // The minimap is created once the map has been loaded
+            let minimap_sheet = if self.minimap.is_none() {
+                Some(SpriteSheet::from_texture(state.assets().texture(self.sprites).clone(), SPRITE_SIZE))
+
+            } else {
+                None
+            };
+
+            // Building placement
+            let placement = if keyboard.was_pressed(Key::B) {
+                state.definitions().building_kind("House")
+
+            } else if keyboard.was_pressed(Key::G) {
+                state.definitions().building_kind("Barracks")
+
+            } else {
+                None
+            };
+
+            if placement.is_some() {
+                self.placing = if self.placing == placement { None } else { placement };
+            }
+
+            // Map
+            let (left, released, right, hover, view) = if let Some(ref mut terrain) = state.terrain {
+
+                if let Some(sheet) = minimap_sheet {
+                    let size = (terrain.source().width, terrain.source().height);
+                    self.minimap = Some(Minimap::new(&mut self.target, size, sheet));
+                }
+
+                // Clicking or dragging on the minimap centers the view on the
+                // tile below the mouse
+                let minimap = self.minimap.as_mut().unwrap();
+                if let Some((x, y)) = minimap.drag(mouse) {
+                    self.scroll = (
+                        (x as f32 * TILE_SIZE) as i32 - self.target.width as i32 / 2,
+                        (y as f32 * TILE_SIZE) as i32 - self.target.height as i32 / 2
+                    );
+                }
+
+                self.scroll = terrain.scroll_to(self.scroll.0, self.scroll.1);
 
-            self.scroll = terrain.scroll_to(self.scroll.0, self.scroll.1);
+                minimap.update_terrain(&mut encoder, terrain);
+                if let Some((generation, shades)) = shades {
+                    minimap.set_fog(&mut encoder, &shades);
+                    terrain.set_overlay(shades);
+                    self.fog_generation = Some(generation);
+                }
+
+                terrain.animate(time);
+                terrain.draw(&mut encoder);
+
+                // Input
+                let left = if mouse.was_pressed(Button::Left) && !minimap.is_dragging() {
+                    let (x, y) = mouse.get(Button::Left).position();
+                    Some(((x, y), terrain.screen_to_grid(x, y)))
+
+                } else {
+                    None
+                };
+
+                // Cells which are currently visible on screen
+                let view = (
+                    terrain.screen_to_grid(0, 0),
+                    terrain.screen_to_grid(self.target.width as i32 - 1, self.target.height as i32 - 1)
+                );
+
+                // Drag selections are finished by releasing the left button
+                let released = if mouse.was_released(Button::Left) {
+                    let (x, y) = mouse.get(Button::Left).position();
+                    let start = self.selection.drag_start((x, y));
+                    let click = self.selection.end_drag().map(|_| terrain.screen_to_grid(x, y));
+                    Some((start.map(|(sx, sy)| terrain.screen_to_grid(sx, sy)), click, view))
 
-            terrain.draw(&mut encoder);
+                } else {
+                    None
+                };
 
-            // Input
-            if mouse.was_pressed(Button::Left) {
-                let (x, y) = mouse.get(Button::Left).position();
-                let p = terrain.screen_to_grid(x, y);
-                Some(GameInput::LeftClick(p.0 as u8, p.1 as u8))
+                let right = if mouse.was_pressed(Button::Right) {
+                    let (x, y) = mouse.get(Button::Right).position();
+                    Some(terrain.screen_to_grid(x, y))
+
+                } else {
+                    None
+                };
+
+                let (x, y) = mouse.position();
+                (left, released, right, Some(terrain.screen_to_grid(x, y)), Some(view))
+
+            } else {
+                (None, None, None, None, None)
+            };
+
+            // Units
+            let fog = state.fog();
+            let is_visible = |cell: GridCell| local.map(|p| fog.is_visible(p, cell)).unwrap_or(false);
+
+            // Ghost preview of the building which is about to be placed
+            let ghost = if let (Some(kind), Some(p)) = (self.placing, hover) {
+                state.definitions().building(kind).map(|d| {
+                    let origin = GridCell(p.0, p.1);
+                    let explored = d.cells(origin).into_iter().all(|c| {
+                        local.map(|l| fog.is_explored(l, c)).unwrap_or(false)
+                    });
+                    (kind, origin, d.footprint, explored && state.can_place_building(kind, origin))
+                })
 
             } else {
                 None
+            };
+
+            if let Some((kind, origin, _, valid)) = ghost {
+
+                if left.is_some() {
+                    if valid {
+                        inputs.push(GameInput::PlaceBuilding(kind, origin.0 as u8, origin.1 as u8));
+                    }
+                    self.placing = None;
+
+                } else if right.is_some() {
+                    self.placing = None;
+                }
+
+            // Select own units or buildings
+            } else if let Some((screen, _)) = left {
+                self.selection.begin_drag(screen);
+            }
+
+            let add = keyboard.is_pressed(Key::LShift) || keyboard.is_pressed(Key::RShift);
+            if let (Some(local), Some((start, end, screen))) = (local, released) {
+                match (start, end) {
+                    (Some(from), Some(to)) => self.selection.select_area(state, local, from, to, add),
+                    (None, Some(cell)) => self.selection.click(state, local, cell, screen, add, time),
+                    _ => {}
+                }
+            }
+
+            // Control groups, holding control assigns the current selection
+            let assign = keyboard.is_pressed(Key::LControl) || keyboard.is_pressed(Key::RControl);
+            for (group, key) in GROUP_KEYS.iter().enumerate() {
+                if keyboard.was_pressed(*key) {
+                    if assign {
+                        self.selection.assign_group(group);
+
+                    } else {
+                        self.selection.recall_group(group, add);
+                    }
+                }
+            }
+
+            // Drop selections of units and buildings which no longer exist
+            self.selection.retain(state);
+
+            if let (Some(p), None) = (right, ghost) {
+
+                // Right clicking a visible enemy attacks it
+                let cell = GridCell(p.0, p.1);
+                let enemy = if is_visible(cell) {
+                    state.units().iter().find(|u| Some(u.owner) != local && u.position() == cell).map(|u| Target::Unit(u.id)).or_else(|| {
+                        state.buildings().iter().find(|b| Some(b.owner) != local && b.contains(cell)).map(|b| Target::Building(b.id))
+                    })
+
+                } else {
+                    None
+                };
+
+                for id in self.selection.units() {
+                    if let Some(target) = enemy {
+                        inputs.push(GameInput::Attack(*id, target));
+
+                    } else {
+                        inputs.push(GameInput::MoveUnit(*id, p.0 as u8, p.1 as u8));
+                    }
+                }
+                if let Some(id) = self.selection.building() {
+                    inputs.push(GameInput::SetRally(id, p.0 as u8, p.1 as u8));
+                }
+            }
+
+            // Production
+            if let Some(b) = self.selection.building().and_then(|id| state.buildings().iter().find(|b| b.id == id)) {
+                if keyboard.was_pressed(Key::P) {
+                    let produces = state.definitions().building(b.kind).and_then(|d| d.produces.first().cloned());
+                    if let Some(kind) = produces {
+                        inputs.push(GameInput::QueueUnit(b.id, kind));
+                    }
+
+                } else if keyboard.was_pressed(Key::C) {
+                    inputs.push(GameInput::CancelUnit(b.id));
+                }
+            }
+
+            self.sprite_view.scroll_to(self.scroll.0, self.scroll.1);
+
+            // Buildings
+            let mut structure = Sprite::new();
+            for b in state.buildings() {
+
+                if Some(b.owner) != local && !b.cells().into_iter().any(|c| is_visible(c)) {
+                    continue;
+                }
+
+                let (w, h) = b.footprint();
+                let origin = b.origin();
+                let (x, y) = (origin.0 as f32 * TILE_SIZE, origin.1 as f32 * TILE_SIZE);
+
+                if self.selection.building() == Some(b.id) {
+                    structure.set_size(w as f32 * TILE_SIZE + 4.0, h as f32 * TILE_SIZE + 4.0);
+                    structure.set_position(x - 2.0, y - 2.0);
+                    structure.set_tile(9);
+                    structure.set_layer(LAYER_SELECTION);
+                    self.sprite_view.draw_sprite(&structure);
+                }
+
+                structure.set_size(w as f32 * TILE_SIZE, h as f32 * TILE_SIZE);
+                structure.set_position(x, y);
+                structure.set_tile(if b.is_complete() { 4 } else { 1 });
+                structure.set_layer(LAYER_UNITS);
+                self.sprite_view.draw_sprite(&structure);
+
+                if let Some(d) = state.definitions().building(b.kind) {
+                    draw_health_bar(&mut self.sprite_view, x, y, w as f32 * TILE_SIZE, b.health, d.health);
+                }
+
+            }
+
+            if let Some((_, origin, (w, h), valid)) = ghost {
+                structure.set_size(w as f32 * TILE_SIZE, h as f32 * TILE_SIZE);
+                structure.set_position(origin.0 as f32 * TILE_SIZE, origin.1 as f32 * TILE_SIZE);
+                structure.set_tile(if valid { 3 } else { 2 });
+                structure.set_layer(LAYER_DECALS);
+                self.sprite_view.draw_sprite(&structure);
+            }
+
+            let mut sprite = Sprite::new();
+            sprite.set_size(TILE_SIZE, TILE_SIZE);
+            sprite.set_tile(8);
+            sprite.set_layer(LAYER_UNITS);
+
+            let mut selection = Sprite::new();
+            selection.set_size(TILE_SIZE + 4.0, TILE_SIZE + 4.0);
+            selection.set_tile(9);
+            selection.set_layer(LAYER_SELECTION);
+
+            let positions = &self.positions;
+            for unit in state.units() {
+
+                // Enemy units are only drawn while they are within sight
+                if Some(unit.owner) != local && !is_visible(unit.position()) {
+                    continue;
+                }
+
+                let (x, y) = positions.get(&unit.id).map(|&(from, to)| {
+                    lerp(from, to, alpha)
+
+                }).unwrap_or_else(|| unit.world_position(TILE_SIZE));
+                if self.selection.contains(unit.id) {
+                    selection.set_position(x - 2.0, y - 2.0);
+                    self.sprite_view.draw_sprite(&selection);
+                }
+                sprite.set_position(x, y);
+                self.sprite_view.draw_sprite(&sprite);
+
+                if let Some(d) = state.definitions().unit(unit.kind) {
+                    draw_health_bar(&mut self.sprite_view, x, y, TILE_SIZE, unit.health, d.health);
+                }
+            }
+
+            let mut shot = Sprite::new();
+            shot.set_size(PROJECTILE_SIZE, PROJECTILE_SIZE);
+            shot.set_tile(6);
+            shot.set_layer(LAYER_PROJECTILES);
+
+            for projectile in state.projectiles() {
+                let (x, y) = projectile.world_position(TILE_SIZE);
+                let offset = (TILE_SIZE - PROJECTILE_SIZE) * 0.5;
+                shot.set_position(x + offset, y + offset);
+                self.sprite_view.draw_sprite(&shot);
+            }
+
+            // Rubber band of the current drag selection
+            let (mx, my) = mouse.position();
+            if let Some((sx, sy)) = self.selection.drag_start((mx, my)) {
+                let mut band = Sprite::new();
+                band.set_size((mx - sx).abs() as f32, (my - sy).abs() as f32);
+                band.set_position((sx.min(mx) + self.scroll.0) as f32, (sy.min(my) + self.scroll.1) as f32);
+                band.set_tile(9);
+                band.set_layer(LAYER_UI);
+                self.sprite_view.draw_sprite(&band);
             }
 
-        } else {
-            None
-        };
+            self.sprite_view.draw(&mut self.target.factory, &mut encoder);
+
+            if let (Some(minimap), Some(view)) = (self.minimap.as_mut(), view) {
+                minimap.draw(&mut self.target.factory, &mut encoder, state, local, view);
+            }
 
-        if let Some(input) = input {
+        }
+
+        for input in inputs {
             self.client.queue_input(input);
         }
 
@@ -132,3 +696,46 @@ impl Renderable for Game {
 
 }
 
+
+// Helpers --------------------------------------------------------------------
+
+/// Draws a bar above damaged units and buildings.
+fn draw_health_bar(view: &mut SpriteView, x: f32, y: f32, width: f32, health: u16, max: u16) {
+    if health < max {
+        let mut bar = Sprite::new();
+        bar.set_size(width, HEALTH_BAR_HEIGHT);
+        bar.set_position(x, y - HEALTH_BAR_HEIGHT);
+        bar.set_tile(2);
+        bar.set_layer(LAYER_UI);
+        view.draw_sprite(&bar);
+
+        bar.set_size(width * health as f32 / max as f32, HEALTH_BAR_HEIGHT);
+        bar.set_tile(3);
+        view.draw_sprite(&bar);
+    }
+}
+
+fn print_results(state: &GameState) {
+    if let Some(outcome) = state.outcome() {
+        println!("[Game] Results after {} ticks:", outcome.tick);
+        for p in state.players() {
+            println!(
+                "[Game]   Player {:?}: {}, gathered {}, produced {} unit(s), lost {} unit(s) and {} building(s)",
+                p.id.0,
+                if outcome.winners.contains(&p.id) { "won" } else { "lost" },
+                p.stats.gathered,
+                p.stats.units_produced,
+                p.stats.units_lost,
+                p.stats.buildings_lost
+            );
+        }
+    }
+}
+
+fn lerp(from: (f32, f32), to: (f32, f32), alpha: f32) -> (f32, f32) {
+    (from.0 + (to.0 - from.0) * alpha, from.1 + (to.1 - from.1) * alpha)
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1000000) as u64
+}
@@ -0,0 +1,199 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// External Dependencies ------------------------------------------------------
+use tiles::GridCell;
+
+
+// Internal Dependencies ------------------------------------------------------
+use core::{BuildingID, GameState, PlayerID, Unit, UnitID, UnitKind};
+
+
+// Statics --------------------------------------------------------------------
+const DRAG_THRESHOLD: i32 = 4;
+const DOUBLE_CLICK_MS: u64 = 300;
+const CONTROL_GROUPS: usize = 10;
+
+
+// Selection ------------------------------------------------------------------
+
+/// The units or building currently selected by the local player.
+///
+/// Selections never leave the client, commands only carry the IDs of the
+/// selected units.
+pub struct Selection {
+    units: Vec<UnitID>,
+    building: Option<BuildingID>,
+    groups: Vec<Vec<UnitID>>,
+    drag: Option<(i32, i32)>,
+    last_click: Option<(u64, UnitKind)>
+}
+
+impl Selection {
+
+    pub fn new() -> Self {
+        Self {
+            units: Vec::new(),
+            building: None,
+            groups: vec![Vec::new(); CONTROL_GROUPS],
+            drag: None,
+            last_click: None
+        }
+    }
+
+    pub fn units(&self) -> &[UnitID] {
+        &self.units
+    }
+
+    pub fn building(&self) -> Option<BuildingID> {
+        self.building
+    }
+
+    pub fn contains(&self, id: UnitID) -> bool {
+        self.units.contains(&id)
+    }
+
+    // Dragging ---------------------------------------------------------------
+    pub fn begin_drag(&mut self, screen: (i32, i32)) {
+        self.drag = Some(screen);
+    }
+
+    pub fn end_drag(&mut self) -> Option<(i32, i32)> {
+        self.drag.take()
+    }
+
+    /// Returns the screen position the drag started at, once the mouse has
+    /// moved far enough for it to no longer count as a click.
+    pub fn drag_start(&self, screen: (i32, i32)) -> Option<(i32, i32)> {
+        self.drag.and_then(|(x, y)| {
+            if (screen.0 - x).abs() > DRAG_THRESHOLD || (screen.1 - y).abs() > DRAG_THRESHOLD {
+                Some((x, y))
+
+            } else {
+                None
+            }
+        })
+    }
+
+    // Selecting --------------------------------------------------------------
+
+    /// Selects the unit or building at `cell`.
+    ///
+    /// Double clicking a unit selects all units of the same type within
+    /// `screen`, `add` extends or toggles the current selection instead.
+    pub fn click(
+        &mut self,
+        state: &GameState,
+        local: PlayerID,
+        cell: (i32, i32),
+        screen: ((i32, i32), (i32, i32)),
+        add: bool,
+        time: u64
+    ) {
+
+        let hit = state.units().iter().find(|u| {
+            u.owner == local && u.position().0 == cell.0 && u.position().1 == cell.1
+        });
+
+        if let Some(unit) = hit {
+
+            let double = self.last_click.map(|(t, kind)| {
+                kind == unit.kind && time.saturating_sub(t) <= DOUBLE_CLICK_MS
+
+            }).unwrap_or(false);
+
+            if double {
+                let kind = unit.kind;
+                let units = own_units_within(state, local, screen.0, screen.1).into_iter().filter(|u| {
+                    u.kind == kind
+
+                }).map(|u| u.id).collect();
+                self.select(units, add);
+                self.last_click = None;
+
+            } else {
+                if add && self.contains(unit.id) {
+                    self.units.retain(|id| *id != unit.id);
+
+                } else {
+                    self.select(vec![unit.id], add);
+                }
+                self.last_click = Some((time, unit.kind));
+            }
+
+        } else if !add {
+            self.units.clear();
+            self.building = state.buildings().iter().find(|b| {
+                b.owner == local && b.contains(GridCell(cell.0, cell.1))
+
+            }).map(|b| b.id);
+            self.last_click = None;
+        }
+
+    }
+
+    /// Selects all own units within the rectangle spanned by two cells.
+    pub fn select_area(&mut self, state: &GameState, local: PlayerID, from: (i32, i32), to: (i32, i32), add: bool) {
+        let units = own_units_within(state, local, from, to).into_iter().map(|u| u.id).collect();
+        self.select(units, add);
+    }
+
+    /// Drops all units and buildings which no longer exist.
+    pub fn retain(&mut self, state: &GameState) {
+        let exists = |id: &UnitID| state.units().iter().any(|u| u.id == *id);
+        self.units.retain(&exists);
+        for group in &mut self.groups {
+            group.retain(&exists);
+        }
+        if let Some(id) = self.building {
+            if !state.buildings().iter().any(|b| b.id == id) {
+                self.building = None;
+            }
+        }
+    }
+
+    // Control Groups ---------------------------------------------------------
+    pub fn assign_group(&mut self, group: usize) {
+        if let Some(g) = self.groups.get_mut(group) {
+            *g = self.units.clone();
+        }
+    }
+
+    pub fn recall_group(&mut self, group: usize, add: bool) {
+        let units = self.groups.get(group).cloned().unwrap_or_else(Vec::new);
+        if !units.is_empty() {
+            self.select(units, add);
+        }
+    }
+
+    fn select(&mut self, units: Vec<UnitID>, add: bool) {
+        if !add {
+            self.units.clear();
+        }
+        for id in units {
+            if !self.units.contains(&id) {
+                self.units.push(id);
+            }
+        }
+        self.building = None;
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn own_units_within(state: &GameState, local: PlayerID, from: (i32, i32), to: (i32, i32)) -> Vec<&Unit> {
+    let (x0, x1) = (from.0.min(to.0), from.0.max(to.0));
+    let (y0, y1) = (from.1.min(to.1), from.1.max(to.1));
+    state.units().iter().filter(|u| {
+        let p = u.position();
+        u.owner == local && p.0 >= x0 && p.0 <= x1 && p.1 >= y0 && p.1 <= y1
+
+    }).collect()
+}
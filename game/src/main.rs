@@ -10,31 +10,49 @@
 // Crates ---------------------------------------------------------------------
 extern crate rand;
 extern crate serde;
+extern crate toml;
 extern crate clockwork;
 #[macro_use]
 extern crate serde_derive;
 
 extern crate audio;
 extern crate tiles;
+extern crate sprites;
 extern crate client;
 extern crate renderer;
 
 
+// STD Dependencies -----------------------------------------------------------
+use std::path::{Path, PathBuf};
+use std::net::SocketAddr;
+
+
 // External Dependencies ------------------------------------------------------
 use rand::Rng;
 
 
 // Internal Dependencies ------------------------------------------------------
 mod core;
+mod editor;
 mod game;
+use editor::Editor;
 use game::Game;
-use core::GameOptions;
+use core::{MAP_PORT_OFFSET, Assets, Difficulty, GameOptions, MapBundle, Victory, serve_map};
 
 
 // Main -----------------------------------------------------------------------
 pub fn main() {
 
-    client::start(|config, min_players, client| {
+    client::start(|config, arguments, client| {
+
+        let root = arguments.assets.as_ref().map(PathBuf::from).unwrap_or_else(Assets::find_root);
+        if arguments.editor {
+            renderer::run::<Editor, _>("RTS Editor", 640, 480, 60, config.high_tick_rate as u32, |refs| {
+                Editor::new(refs, Assets::new(&root), &Path::new("maps").join(format!("{}.tmx", arguments.map)))
+            });
+            return;
+        }
+
         renderer::run::<Game, _>("RTS", 640, 480, 60, config.high_tick_rate as u32, move |refs| {
 
             // Create a seed for the RNG
@@ -42,11 +60,48 @@ pub fn main() {
             rand::thread_rng().fill_bytes(&mut seed[..]);
             println!("Seed is: {:?}", seed);
 
-            let options = GameOptions {
-                min_players: min_players,
-                random_seed: seed
+            let mut options = GameOptions {
+                min_players: arguments.min_players,
+                random_seed: seed,
+                victory: Victory {
+                    annihilation: arguments.annihilation,
+                    headquarters: arguments.headquarters,
+                    resources: arguments.resource_goal,
+                    time_limit: arguments.time_limit
+                },
+                ai_players: arguments.ai_players,
+                ai_difficulty: Difficulty::from_u8(arguments.ai_difficulty),
+                generate_map: arguments.generate_map,
+                map_name: [0; 32],
+                map_hash: 0
             };
 
+            // Only the options of the host are used, so other clients do
+            // not need to have the map themselves
+            options.set_map_name(&arguments.map);
+            if !arguments.generate_map {
+                match MapBundle::load(&root, &arguments.map) {
+                    Ok(bundle) => {
+                        options.map_hash = bundle.hash();
+                        if arguments.is_host {
+                            serve_map(config.server_addr.port() + MAP_PORT_OFFSET, bundle).expect("[Game] Failed to start map server.");
+                        }
+                    },
+                    Err(err) => if arguments.is_host {
+                        panic!("[Game] Failed to load map \"{}\": {}", arguments.map, err);
+                    }
+                }
+            }
+
+            let mut client = client;
+            client.state().set_assets(Assets::new(&root));
+
+            // Missing maps are downloaded from the host
+            client.state().set_map_host(SocketAddr::new(
+                config.remote_addr.ip(),
+                config.remote_addr.port() + MAP_PORT_OFFSET
+            ));
+
             Game::new(client, options, refs)
 
         });
@@ -30,7 +30,7 @@ pub trait WithPosition {
 
 
 // Keyboad --------------------------------------------------------------------
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Key {
     W = 0,
     A = 1,
@@ -40,7 +40,24 @@ pub enum Key {
     G = 5,
     B = 6,
     C = 7,
-    Unknown = 8
+    Key0 = 8,
+    Key1 = 9,
+    Key2 = 10,
+    Key3 = 11,
+    Key4 = 12,
+    Key5 = 13,
+    Key6 = 14,
+    Key7 = 15,
+    Key8 = 16,
+    Key9 = 17,
+    LShift = 18,
+    RShift = 19,
+    LControl = 20,
+    RControl = 21,
+    T = 22,
+    Y = 23,
+    Z = 24,
+    Unknown = 25
 }
 
 impl From<VirtualKeyCode> for Key {
@@ -54,6 +71,23 @@ impl From<VirtualKeyCode> for Key {
             VirtualKeyCode::G => Key::G,
             VirtualKeyCode::B => Key::B,
             VirtualKeyCode::C => Key::C,
+            VirtualKeyCode::Key0 => Key::Key0,
+            VirtualKeyCode::Key1 => Key::Key1,
+            VirtualKeyCode::Key2 => Key::Key2,
+            VirtualKeyCode::Key3 => Key::Key3,
+            VirtualKeyCode::Key4 => Key::Key4,
+            VirtualKeyCode::Key5 => Key::Key5,
+            VirtualKeyCode::Key6 => Key::Key6,
+            VirtualKeyCode::Key7 => Key::Key7,
+            VirtualKeyCode::Key8 => Key::Key8,
+            VirtualKeyCode::Key9 => Key::Key9,
+            VirtualKeyCode::LShift => Key::LShift,
+            VirtualKeyCode::RShift => Key::RShift,
+            VirtualKeyCode::LControl => Key::LControl,
+            VirtualKeyCode::RControl => Key::RControl,
+            VirtualKeyCode::T => Key::T,
+            VirtualKeyCode::Y => Key::Y,
+            VirtualKeyCode::Z => Key::Z,
             _ => Key::Unknown
         }
     }
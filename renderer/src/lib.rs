@@ -53,7 +53,9 @@ pub type Factory = gfx_device_gl::Factory;
 // Traits ---------------------------------------------------------------------
 pub trait Renderable {
     fn tick(&mut self, time: u64) where Self: Sized;
-    fn draw(&mut self, time: u64, encoder: &mut Encoder, &Keyboard, &Mouse) where Self: Sized;
+    /// `alpha` is the fraction of the tick interval that has passed since
+    /// the last call to `tick`, ranging from `0.0` to `1.0`.
+    fn draw(&mut self, time: u64, alpha: f32, encoder: &mut Encoder, &Keyboard, &Mouse) where Self: Sized;
 }
 
 pub struct RenderTarget {
@@ -177,8 +179,11 @@ pub fn run<
         }
 
         // Draw
+        let elapsed = last_tick.elapsed();
+        let alpha = duration_ms(elapsed) as f32 / duration_ms(tick_time) as f32;
+
         encoder.clear(&output_color, [1.0, 0.0, 1.0, 1.0]);
-        renderable.draw(render_time, &mut encoder, &keyboard, &mouse);
+        renderable.draw(render_time, alpha.min(1.0), &mut encoder, &keyboard, &mouse);
         encoder.flush(&mut device);
         window.swap_buffers().unwrap();
         device.cleanup();
@@ -200,3 +205,9 @@ pub fn run<
 
 }
 
+
+
+// Helpers --------------------------------------------------------------------
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1000000) as u64
+}
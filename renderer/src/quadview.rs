@@ -30,6 +30,7 @@ pub struct QuadView {
     view_width: u32,
     view_height: u32,
     dirty: bool,
+    dirty_range: Option<(usize, usize)>
 }
 
 impl QuadView {
@@ -47,13 +48,7 @@ impl QuadView {
         let vertex_count = vertices.len();
 
         let locals_buffer = factory.create_constant_buffer(1);
-        let vertex_buffer = factory.create_buffer::<Vertex>(
-            vertex_count * 4,
-            gfx::buffer::Role::Vertex,
-            gfx::memory::Usage::Dynamic,
-            gfx::Bind::empty()
-
-        ).expect("QuadView: Could not create `vertex_buffer`");
+        let vertex_buffer = QuadView::create_vertex_buffer(factory, vertex_count * 4);
 
         let sampler = factory.create_sampler(
             SamplerInfo::new(FilterMethod::Scale, WrapMode::Tile)
@@ -92,15 +87,30 @@ impl QuadView {
             scale_y: scale_y,
             view_width: view_width,
             view_height: view_height,
-            dirty: true
+            dirty: true,
+            dirty_range: None
         }
 
     }
 
+    /// Draws all quads with another texture, e.g. after it was reloaded.
+    pub fn set_texture(&mut self, texture: gfx::handle::ShaderResourceView<gfx_device_gl::Resources, [f32; 4]>) {
+        self.data.tex.0 = texture;
+    }
+
     pub fn set_dirty(&mut self) {
         self.dirty = true;
     }
 
+    /// Marks the vertices from `start` up to `end` for upload, without
+    /// uploading the whole buffer.
+    pub fn set_dirty_range(&mut self, start: usize, end: usize) {
+        self.dirty_range = Some(match self.dirty_range {
+            Some((s, e)) => (s.min(start), e.max(end)),
+            None => (start, end)
+        });
+    }
+
     pub fn scroll_to(&mut self, scroll_x: f32, scroll_y: f32) {
         self.transform.transform[3][0] = scroll_x * self.scale_x;
         self.transform.transform[3][1] = scroll_y * self.scale_y;
@@ -110,11 +120,43 @@ impl QuadView {
         &mut self.vertices[index..index + 6]
     }
 
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Grows the view to hold at least `vertex_count` vertices, recreating
+    /// the vertex buffer if it is too small. Added vertices are empty.
+    pub fn resize(&mut self, factory: &mut gfx_device_gl::Factory, vertex_count: usize) {
+        if vertex_count > self.vertices.len() {
+            self.vertices.resize(vertex_count, Vertex {
+                pos: [0.0, 0.0],
+                uv: [0.0, 0.0],
+                color: [1.0; 4]
+            });
+            self.vertex_buffer = QuadView::create_vertex_buffer(factory, vertex_count);
+            self.data.vbuf = self.vertex_buffer.clone();
+            self.slice.end = vertex_count as u32;
+            self.dirty = true;
+        }
+    }
+
+    /// Draws only the vertices from `start` up to `end`, so ranges of the
+    /// same buffer can be drawn with different textures.
+    pub fn draw_range(&mut self, encoder: &mut gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer>, start: u32, end: u32) {
+        self.slice.start = start;
+        self.draw(encoder, Some(end));
+        self.slice.start = 0;
+    }
+
     pub fn draw(&mut self, encoder: &mut gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer>, vertices_limit: Option<u32>) {
 
         if self.dirty {
             self.dirty = false;
+            self.dirty_range = None;
             encoder.update_buffer(&self.vertex_buffer, &self.vertices, 0).ok();
+
+        } else if let Some((start, end)) = self.dirty_range.take() {
+            encoder.update_buffer(&self.vertex_buffer, &self.vertices[start..end], start).ok();
         }
 
         encoder.update_buffer(&self.data.transform, &[self.transform], 0).ok();
@@ -131,6 +173,20 @@ impl QuadView {
         self.pso = QuadView::create_pipeline(factory, active);
     }
 
+    fn create_vertex_buffer(
+        factory: &mut gfx_device_gl::Factory,
+        vertex_count: usize
+
+    ) -> gfx::handle::Buffer<gfx_device_gl::Resources, Vertex> {
+        factory.create_buffer::<Vertex>(
+            vertex_count,
+            gfx::buffer::Role::Vertex,
+            gfx::memory::Usage::Dynamic,
+            gfx::Bind::empty()
+
+        ).expect("QuadView: Could not create `vertex_buffer`")
+    }
+
     fn create_pipeline(
         factory: &mut gfx_device_gl::Factory,
         wireframe: bool
@@ -175,6 +231,7 @@ gfx_defines!{
     vertex Vertex {
         pos: [f32; 2] = "pos",
         uv: [f32; 2] = "uv",
+        color: [f32; 4] = "color",
     }
 
     constant Transform {
@@ -195,8 +252,10 @@ pub static VERTEX_SHADER_150: &'static [u8] = br#"
     #version 150 core
     in vec2 pos;
     in vec2 uv;
+    in vec4 color;
 
     out vec2 v_Uv;
+    out vec4 v_Color;
 
     uniform Transform {
         mat4 u_View;
@@ -205,6 +264,7 @@ pub static VERTEX_SHADER_150: &'static [u8] = br#"
     void main() {
         gl_Position = u_View * vec4(pos, 0.0, 1.0);
         v_Uv = uv;
+        v_Color = color;
     }
 "#;
 
@@ -213,10 +273,11 @@ pub static FRAGMENT_SHADER_150: &'static [u8] = br#"
 
     uniform sampler2D t_Texture;
     in vec2 v_Uv;
+    in vec4 v_Color;
     out vec4 o_Color;
 
     void main() {
-        o_Color = texture(t_Texture, v_Uv);
+        o_Color = texture(t_Texture, v_Uv) * v_Color;
     }
 "#;
 
@@ -20,9 +20,10 @@ use image;
 
 
 // Texture Abstraction --------------------------------------------------------
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Texture {
     view: gfx::handle::ShaderResourceView<gfx_device_gl::Resources, [f32; 4]>,
+    texture: Option<gfx::handle::Texture<gfx_device_gl::Resources, gfx::format::R8_G8_B8_A8>>,
     size: (u32, u32)
 }
 
@@ -37,10 +38,73 @@ impl Texture {
 
         Ok(Self {
             view: view,
+            texture: None,
             size: (width, height)
         })
     }
 
+    pub fn from_rgba(factory: &mut gfx_device_gl::Factory, size: (u32, u32), pixels: &[u8]) -> Result<Self, Box<Error>> {
+
+        let kind = gfx::texture::Kind::D2(size.0 as u16, size.1 as u16, gfx::texture::AaMode::Single);
+        let (_, view) = factory.create_texture_immutable_u8::<gfx::format::Srgba8>(kind, &[pixels])?;
+
+        Ok(Self {
+            view: view,
+            texture: None,
+            size: size
+        })
+    }
+
+    /// Creates an empty texture whose pixels can later be replaced via
+    /// `update`.
+    pub fn dynamic(factory: &mut gfx_device_gl::Factory, size: (u32, u32)) -> Result<Self, Box<Error>> {
+
+        let kind = gfx::texture::Kind::D2(size.0 as u16, size.1 as u16, gfx::texture::AaMode::Single);
+        let texture = factory.create_texture::<gfx::format::R8_G8_B8_A8>(
+            kind,
+            1,
+            gfx::SHADER_RESOURCE | gfx::TRANSFER_DST,
+            gfx::memory::Usage::Dynamic,
+            Some(gfx::format::ChannelType::Srgb)
+        )?;
+
+        let view = factory.view_texture_as_shader_resource::<gfx::format::Srgba8>(
+            &texture,
+            (0, 0),
+            gfx::format::Swizzle::new()
+        )?;
+
+        Ok(Self {
+            view: view,
+            texture: Some(texture),
+            size: size
+        })
+    }
+
+    /// Replaces the pixels within the given rectangle of a dynamic texture,
+    /// `pixels` are expected in row order.
+    pub fn update(
+        &self,
+        encoder: &mut gfx::Encoder<gfx_device_gl::Resources, gfx_device_gl::CommandBuffer>,
+        offset: (u32, u32),
+        size: (u32, u32),
+        pixels: &[[u8; 4]]
+    ) {
+        if let Some(ref texture) = self.texture {
+            let info = gfx::texture::ImageInfoCommon {
+                xoffset: offset.0 as u16,
+                yoffset: offset.1 as u16,
+                zoffset: 0,
+                width: size.0 as u16,
+                height: size.1 as u16,
+                depth: 0,
+                format: (),
+                mipmap: 0
+            };
+            encoder.update_texture::<gfx::format::R8_G8_B8_A8, gfx::format::Srgba8>(texture, None, info, pixels).ok();
+        }
+    }
+
     pub fn bind(&self) -> gfx::handle::ShaderResourceView<gfx_device_gl::Resources, [f32; 4]> {
         self.view.clone()
     }
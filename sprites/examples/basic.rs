@@ -18,8 +18,8 @@ use std::path::Path;
 
 
 // External Dependencies ------------------------------------------------------
-use sprites::{SpriteSheet, SpriteView, Sprite};
-use renderer::{Key, Keyboard, Button, Mouse, Renderable, Encoder};
+use sprites::{Animation, SpriteSheet, SpriteView, Sprite};
+use renderer::{Key, Keyboard, Button, Mouse, Renderable, Encoder, Factory};
 
 
 // Structs --------------------------------------------------------------------
@@ -30,7 +30,7 @@ pub struct Unit {
 
 impl Unit {
 
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32, idle: Option<&Animation>, time: u64) -> Self {
 
         let mut sprite = Sprite::new();
         sprite.set_tile_size(2, 2);
@@ -38,6 +38,10 @@ impl Unit {
         sprite.set_position(x, y);
         sprite.set_tile(8);
 
+        if let Some(animation) = idle {
+            sprite.play(animation, time);
+        }
+
         Self {
             sprite: sprite,
             selected: false
@@ -69,7 +73,9 @@ impl Unit {
 
 // Example --------------------------------------------------------------------
 struct Demo {
+    factory: Factory,
     view: SpriteView,
+    idle: Option<Animation>,
     units: Vec<Unit>,
     cursor: Sprite,
     scroll: (i32, i32)
@@ -77,13 +83,15 @@ struct Demo {
 
 impl Demo {
 
-    fn new(mut view: SpriteView) -> Self {
+    fn new(factory: Factory, view: SpriteView, idle: Option<Animation>) -> Self {
 
         let mut cursor = Sprite::new();
         cursor.set_size(32.0, 32.0);
 
         Self {
+            factory: factory,
             view: view,
+            idle: idle,
             units: Vec::new(),
             cursor: cursor,
             scroll: (0, 0)
@@ -94,11 +102,11 @@ impl Demo {
 
 impl Renderable for Demo {
 
-    fn tick(&mut self) where Self: Sized {
+    fn tick(&mut self, _: u64) where Self: Sized {
 
     }
 
-    fn draw(&mut self, mut encoder: &mut Encoder, keyboard: &Keyboard, mouse: &Mouse) where Self: Sized {
+    fn draw(&mut self, time: u64, _: f32, mut encoder: &mut Encoder, keyboard: &Keyboard, mouse: &Mouse) where Self: Sized {
 
         // Scrolling
         if keyboard.is_pressed(Key::A) {
@@ -132,7 +140,7 @@ impl Renderable for Demo {
             }
 
             if !any {
-                self.units.push(Unit::new(x as f32, y as f32));
+                self.units.push(Unit::new(x as f32, y as f32, self.idle.as_ref(), time));
             }
 
         }
@@ -164,8 +172,9 @@ impl Renderable for Demo {
 
         self.view.draw_sprite(&self.cursor);
 
+        self.view.set_time(time);
         self.view.scroll_to(self.scroll.0, self.scroll.1);
-        self.view.draw(&mut encoder);
+        self.view.draw(&mut self.factory, &mut encoder);
 
     }
 
@@ -175,17 +184,24 @@ impl Renderable for Demo {
 fn main() {
     renderer::run::<Demo, _>("Sprites", 640, 480, 60, 10, |mut target| {
 
-        let sheet = SpriteSheet::new(&mut target.factory, &Path::new("../assets/textures/tileset.png"), 16).unwrap();
+        // Units play the idle animation from the example's metadata
+        let sheet = SpriteSheet::new(
+            &mut target.factory,
+            &Path::new("../assets/textures/tileset.png"),
+            16
+
+        ).and_then(|s| s.with_metadata(&Path::new("examples/basic.json"))).unwrap();
+        let idle = sheet.animation("idle", None).cloned();
+
         let view = SpriteView::new(
             &mut target.factory,
             target.color.clone(),
             target.width,
             target.height,
-            sheet,
-            128
+            sheet
         );
 
-        Demo::new(view)
+        Demo::new(target.factory, view, idle)
 
     });
 }
@@ -7,18 +7,170 @@
 // except according to those terms.
 
 
-// STD Dependencies -----------------------------------------------------------
+// Playback Modes -------------------------------------------------------------
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackMode {
+    /// Starts over after the last frame.
+    Loop,
+    /// Plays the frames forwards and then backwards again.
+    PingPong,
+    /// Stops on the last frame.
+    Once
+}
+
+impl Default for PlaybackMode {
+    fn default() -> Self {
+        PlaybackMode::Loop
+    }
+}
+
 
+// Directions -----------------------------------------------------------------
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest
+}
+
+impl Direction {
 
-// External Dependencies ------------------------------------------------------
+    /// Returns the direction of a movement in screen space, where `y` grows
+    /// downwards.
+    pub fn from_offset(x: i32, y: i32) -> Option<Direction> {
+        match (x.signum(), y.signum()) {
+            (0, -1) => Some(Direction::North),
+            (1, -1) => Some(Direction::NorthEast),
+            (1, 0) => Some(Direction::East),
+            (1, 1) => Some(Direction::SouthEast),
+            (0, 1) => Some(Direction::South),
+            (-1, 1) => Some(Direction::SouthWest),
+            (-1, 0) => Some(Direction::West),
+            (-1, -1) => Some(Direction::NorthWest),
+            _ => None
+        }
+    }
 
+}
 
-// Internal Dependencies ------------------------------------------------------
 
+// Animation Abstraction ------------------------------------------------------
 
-// Animation Abstraction --------------------------------------------------------
-#[derive(Debug)]
+/// A named sequence of sheet tiles, each shown for its duration in
+/// milliseconds.
+///
+/// Sheets may contain one animation of the same name per direction, an
+/// animation without a direction is used for all directions which have none
+/// of their own.
+#[derive(Debug, Clone)]
 pub struct Animation {
+    name: String,
+    direction: Option<Direction>,
+    mode: PlaybackMode,
+    frames: Vec<(u32, u32)>,
+    duration: u64
+}
+
+impl Animation {
+
+    /// `frames` are pairs of tile index and duration.
+    pub fn new(name: &str, direction: Option<Direction>, mode: PlaybackMode, frames: Vec<(u32, u32)>) -> Self {
+        let duration = frames.iter().map(|&(_, d)| d as u64).sum();
+        Self {
+            name: name.to_string(),
+            direction: direction,
+            mode: mode,
+            frames: frames,
+            duration: duration
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// Duration of a single pass over all frames.
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// Whether an animation which plays only once has shown its last frame
+    /// for its whole duration.
+    pub fn is_finished(&self, elapsed: u64) -> bool {
+        self.mode == PlaybackMode::Once && elapsed >= self.duration
+    }
+
+    /// Returns the tile which is shown after the animation played for
+    /// `elapsed` milliseconds.
+    pub fn frame_at(&self, elapsed: u64) -> u32 {
+
+        if self.frames.is_empty() {
+            return 0;
+
+        } else if self.duration == 0 {
+            return self.frames[0].0;
+        }
+
+        let last = self.frames.len() - 1;
+        match self.mode {
+            PlaybackMode::Loop => frame_within(&self.frames, elapsed % self.duration),
+            PlaybackMode::Once => if elapsed >= self.duration {
+                self.frames[last].0
+
+            } else {
+                frame_within(&self.frames, elapsed)
+            },
+            PlaybackMode::PingPong => {
+
+                // The first and last frame are not repeated when reversing
+                let reverse: Vec<(u32, u32)> = if last > 1 {
+                    self.frames[1..last].iter().rev().cloned().collect()
+
+                } else {
+                    Vec::new()
+                };
+
+                let back: u64 = reverse.iter().map(|&(_, d)| d as u64).sum();
+                let t = elapsed % (self.duration + back);
+                if t < self.duration {
+                    frame_within(&self.frames, t)
+
+                } else {
+                    frame_within(&reverse, t - self.duration)
+                }
+
+            }
+        }
+
+    }
+
+}
+
 
+// Helpers --------------------------------------------------------------------
+fn frame_within(frames: &[(u32, u32)], time: u64) -> u32 {
+    let mut t = time;
+    for &(tile, duration) in frames {
+        if t < duration as u64 {
+            return tile;
+        }
+        t -= duration as u64;
+    }
+    frames.last().map(|&(tile, _)| tile).unwrap_or(0)
 }
 
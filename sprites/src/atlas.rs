@@ -0,0 +1,186 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::fs;
+use std::cmp;
+use std::path::{Path, PathBuf};
+use std::error::Error;
+
+
+// External Dependencies ------------------------------------------------------
+use image::{self, ImageBuffer, Pixel, Rgba};
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::metadata::{FrameData, MetaData, Metadata, Point, Rect, Size, metadata_path};
+
+
+// Atlas Packing --------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct AtlasOptions {
+    /// Transparent pixels between two frames.
+    pub padding: u32,
+    /// Whether transparent borders are cut off from the frames.
+    pub trim: bool,
+    /// Pivot of all frames, relative to their original size.
+    pub pivot: (f32, f32)
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self {
+            padding: 1,
+            trim: true,
+            pivot: (0.0, 0.0)
+        }
+    }
+}
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Packs all `.png` files of a directory into a single texture and writes
+/// it together with its metadata file.
+///
+/// Frames are listed in the order of their file names, so their indices only
+/// change when files are added or removed. Animations of an existing
+/// metadata file are kept.
+pub fn pack_atlas(input: &Path, output: &Path, options: &AtlasOptions) -> Result<Metadata, Box<Error>> {
+
+    let mut files: Vec<PathBuf> = fs::read_dir(input)?.filter_map(|entry| {
+        entry.ok().map(|e| e.path())
+
+    }).filter(|path| {
+        path.extension().map(|e| e == "png").unwrap_or(false)
+
+    }).collect();
+
+    files.sort();
+
+    if files.is_empty() {
+        return Err(From::from(format!("no .png files in {:?}", input)));
+    }
+
+    // Visible part of each image
+    let mut images: Vec<(String, Image, (u32, u32, u32, u32))> = Vec::with_capacity(files.len());
+    for path in &files {
+        let image = image::open(path)?.to_rgba();
+        let (w, h) = image.dimensions();
+        let rect = if options.trim {
+            visible_rect(&image)
+
+        } else {
+            (0, 0, w, h)
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        images.push((name, image, rect));
+    }
+
+    // Tallest frames first so the shelves waste as little space as possible
+    let mut order: Vec<usize> = (0..images.len()).collect();
+    order.sort_by(|&a, &b| (images[b].2).3.cmp(&(images[a].2).3).then(a.cmp(&b)));
+
+    let padding = options.padding;
+    let area: u32 = images.iter().map(|&(_, _, r)| (r.2 + padding) * (r.3 + padding)).sum();
+    let widest = images.iter().map(|&(_, _, r)| r.2 + padding).max().unwrap_or(1);
+    let width = cmp::max(widest, (area as f64).sqrt().ceil() as u32).next_power_of_two();
+
+    let mut positions = vec![(0, 0); images.len()];
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+    for i in order {
+        let (w, h) = ((images[i].2).2 + padding, (images[i].2).3 + padding);
+        if x + w > width {
+            x = 0;
+            y += shelf;
+            shelf = 0;
+        }
+        positions[i] = (x, y);
+        x += w;
+        shelf = cmp::max(shelf, h);
+    }
+
+    let height = (y + shelf).next_power_of_two();
+
+    // Copy the visible pixels of all frames
+    let mut atlas: Image = ImageBuffer::new(width, height);
+    for (&(_, ref image, rect), &(x, y)) in images.iter().zip(positions.iter()) {
+        for py in 0..rect.3 {
+            for px in 0..rect.2 {
+                atlas.put_pixel(x + px, y + py, *image.get_pixel(rect.0 + px, rect.1 + py));
+            }
+        }
+    }
+
+    atlas.save(output)?;
+
+    let frames = images.iter().zip(positions.iter()).map(|(&(ref name, ref image, rect), &(x, y))| {
+        let (w, h) = image.dimensions();
+        FrameData {
+            filename: name.clone(),
+            frame: Rect { x: x, y: y, w: rect.2, h: rect.3 },
+            rotated: false,
+            trimmed: (rect.2, rect.3) != (w, h),
+            sprite_source_size: Some(Rect { x: rect.0, y: rect.1, w: rect.2, h: rect.3 }),
+            source_size: Some(Size { w: w, h: h }),
+            pivot: Some(Point { x: options.pivot.0, y: options.pivot.1 })
+        }
+
+    }).collect();
+
+    let path = metadata_path(output);
+    let animations = if path.exists() {
+        Metadata::load(&path)?.animations
+
+    } else {
+        Vec::new()
+    };
+
+    let metadata = Metadata {
+        frames: frames,
+        animations: animations,
+        meta: Some(MetaData {
+            image: output.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            size: Size { w: width, h: height }
+        })
+    };
+
+    metadata.save(&path)?;
+    Ok(metadata)
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+
+/// Returns the bounds of all pixels which are not fully transparent, empty
+/// images keep a single pixel.
+fn visible_rect(image: &Image) -> (u32, u32, u32, u32) {
+
+    let (w, h) = image.dimensions();
+    let (mut x0, mut y0, mut x1, mut y1) = (w, h, 0, 0);
+    for y in 0..h {
+        for x in 0..w {
+            if image.get_pixel(x, y).channels()[3] > 0 {
+                x0 = cmp::min(x0, x);
+                y0 = cmp::min(y0, y);
+                x1 = cmp::max(x1, x + 1);
+                y1 = cmp::max(y1, y + 1);
+            }
+        }
+    }
+
+    if x0 < x1 && y0 < y1 {
+        (x0, y0, x1 - x0, y1 - y0)
+
+    } else {
+        (0, 0, cmp::min(w, 1), cmp::min(h, 1))
+    }
+
+}
+
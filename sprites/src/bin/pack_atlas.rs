@@ -0,0 +1,55 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Crates ---------------------------------------------------------------------
+extern crate sprites;
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::env;
+use std::process;
+use std::path::Path;
+
+
+// External Dependencies ------------------------------------------------------
+use sprites::{AtlasOptions, pack_atlas};
+
+
+// Atlas Packer ---------------------------------------------------------------
+fn main() {
+
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 && args.len() != 5 {
+        println!("Usage: pack_atlas <INPUT_DIRECTORY> <OUTPUT_PNG> [PIVOT_X PIVOT_Y]");
+        process::exit(1);
+    }
+
+    let mut options = AtlasOptions::default();
+    if args.len() == 5 {
+        match (args[3].parse::<f32>(), args[4].parse::<f32>()) {
+            (Ok(x), Ok(y)) => options.pivot = (x, y),
+            _ => {
+                println!("[Atlas] Pivot must be two numbers, e.g. 0.5 1.0");
+                process::exit(1);
+            }
+        }
+    }
+
+    let output = Path::new(&args[2]);
+    match pack_atlas(Path::new(&args[1]), output, &options) {
+        Ok(metadata) => {
+            println!("[Atlas] Packed {} frame(s) into {:?}", metadata.frames.len(), output);
+        },
+        Err(err) => {
+            println!("[Atlas] Failed to pack atlas: {}", err);
+            process::exit(1);
+        }
+    }
+
+}
@@ -8,15 +8,25 @@
 
 
 // Crates ---------------------------------------------------------------------
+extern crate image;
 extern crate renderer;
 
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
 
 // Internal Dependencies ------------------------------------------------------
 mod animation;
+mod atlas;
+mod metadata;
 mod sheet;
 mod view;
 
-pub use self::animation::Animation;
-pub use self::sheet::SpriteSheet;
+pub use self::animation::{Animation, Direction, PlaybackMode};
+pub use self::atlas::{AtlasOptions, pack_atlas};
+pub use self::metadata::{Metadata, metadata_path};
+pub use self::sheet::{Frame, SpriteSheet};
 pub use self::view::{Sprite, SpriteView};
 
@@ -0,0 +1,208 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::error::Error;
+
+
+// External Dependencies ------------------------------------------------------
+use serde_json;
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::animation::{Animation, Direction, PlaybackMode};
+use ::sheet::Frame;
+
+
+// Statics --------------------------------------------------------------------
+const DEFAULT_FRAME_DURATION: u32 = 100;
+
+
+// Sheet Metadata -------------------------------------------------------------
+
+/// Returns the path of the metadata file of a texture, which has the same
+/// name but a `.json` extension.
+pub fn metadata_path(texture: &Path) -> PathBuf {
+    texture.with_extension("json")
+}
+
+/// Contents of a sheet metadata file.
+///
+/// Frames use the layout of TexturePacker's "JSON (Array)" format, sheets
+/// without any frames are treated as a uniform grid of tiles. Animations
+/// refer to frames either by their index or by their file name.
+///
+/// ```json
+/// {
+///     "frames": [
+///         {
+///             "filename": "peasant_walk_0.png",
+///             "frame": { "x": 0, "y": 0, "w": 14, "h": 28 },
+///             "spriteSourceSize": { "x": 9, "y": 4, "w": 14, "h": 28 },
+///             "sourceSize": { "w": 32, "h": 32 },
+///             "pivot": { "x": 0.5, "y": 1.0 }
+///         }
+///     ],
+///     "animations": [
+///         { "name": "walk", "direction": "north", "frames": ["peasant_walk_0", 1, 2], "duration": 120 },
+///         { "name": "die", "mode": "once", "frames": [12, 13], "durations": [80, 400] }
+///     ]
+/// }
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    pub frames: Vec<FrameData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub animations: Vec<AnimationData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<MetaData>
+}
+
+impl Metadata {
+
+    pub fn load(path: &Path) -> Result<Self, Box<Error>> {
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<Error>> {
+        Ok(serde_json::to_writer_pretty(File::create(path)?, self)?)
+    }
+
+    /// Rotated frames are not supported and result in an error.
+    pub fn frames(&self) -> Result<Vec<Frame>, Box<Error>> {
+        self.frames.iter().map(|f| -> Result<Frame, Box<Error>> {
+
+            if f.rotated {
+                return Err(From::from(format!("rotated frame \"{}\" is not supported", f.filename)));
+            }
+
+            let offset = f.sprite_source_size.map(|r| (r.x, r.y)).unwrap_or((0, 0));
+            let source = f.source_size.map(|s| (s.w, s.h)).unwrap_or((f.frame.w, f.frame.h));
+            Ok(Frame {
+                name: f.filename.clone(),
+                rect: (f.frame.x, f.frame.y, f.frame.w, f.frame.h),
+                offset: offset,
+                source: source,
+                pivot: f.pivot.map(|p| (p.x, p.y)).unwrap_or((0.0, 0.0))
+            })
+
+        }).collect()
+    }
+
+    /// Animations without any frames, without a duration or with unknown
+    /// frame names are ignored.
+    pub fn animations(&self) -> Vec<Animation> {
+        self.animations.iter().filter_map(|a| {
+
+            let mut frames = Vec::with_capacity(a.frames.len());
+            for (i, frame) in a.frames.iter().enumerate() {
+                let index = match *frame {
+                    FrameRef::Index(index) => index,
+                    FrameRef::Name(ref name) => match self.frame_index(name) {
+                        Some(index) => index,
+                        None => return None
+                    }
+                };
+                frames.push((index, a.durations.get(i).cloned().unwrap_or(a.duration)));
+            }
+
+            let animation = Animation::new(&a.name, a.direction, a.mode, frames);
+            if animation.duration() > 0 {
+                Some(animation)
+
+            } else {
+                None
+            }
+
+        }).collect()
+    }
+
+    fn frame_index(&self, name: &str) -> Option<u32> {
+        self.frames.iter().position(|f| is_frame_name(&f.filename, name)).map(|i| i as u32)
+    }
+
+}
+
+/// Frames can be referred to with or without their file extension.
+pub fn is_frame_name(filename: &str, name: &str) -> bool {
+    filename == name || Path::new(filename).file_stem().map(|s| s == name).unwrap_or(false)
+}
+
+
+// Serialization Structures ---------------------------------------------------
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameData {
+    pub filename: String,
+    pub frame: Rect,
+    #[serde(default)]
+    pub rotated: bool,
+    #[serde(default)]
+    pub trimmed: bool,
+    #[serde(rename = "spriteSourceSize", default, skip_serializing_if = "Option::is_none")]
+    pub sprite_source_size: Option<Rect>,
+    #[serde(rename = "sourceSize", default, skip_serializing_if = "Option::is_none")]
+    pub source_size: Option<Size>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pivot: Option<Point>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationData {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    #[serde(default)]
+    pub mode: PlaybackMode,
+    pub frames: Vec<FrameRef>,
+    #[serde(default = "default_duration")]
+    pub duration: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub durations: Vec<u32>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FrameRef {
+    Index(u32),
+    Name(String)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaData {
+    pub image: String,
+    pub size: Size
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Size {
+    pub w: u32,
+    pub h: u32
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32
+}
+
+fn default_duration() -> u32 {
+    DEFAULT_FRAME_DURATION
+}
+
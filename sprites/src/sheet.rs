@@ -17,13 +17,35 @@ use renderer::{Factory, Texture};
 
 
 // Internal Dependencies ------------------------------------------------------
-use ::animation::Animation;
+use ::animation::{Animation, Direction};
+use ::metadata::{Metadata, is_frame_name, metadata_path};
+
+
+// Atlas Frames ---------------------------------------------------------------
+
+/// A frame of a sprite atlas, all sizes are in pixels.
+///
+/// Frames may be trimmed, in which case `rect` only covers the visible part
+/// of the original image of size `source`, starting at `offset`. The pivot
+/// is relative to the original image and is placed at the sprite's position.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub name: String,
+    pub rect: (u32, u32, u32, u32),
+    pub offset: (u32, u32),
+    pub source: (u32, u32),
+    pub pivot: (f32, f32)
+}
 
 
 // Spritesheet Abstraction ----------------------------------------------------
+
+/// Either a uniform grid of tiles or, when its metadata lists any frames, a
+/// sprite atlas in which tile indices refer to the frames.
 #[derive(Debug)]
 pub struct SpriteSheet {
     animations: Vec<Animation>,
+    frames: Vec<Frame>,
     cols: u32,
     rows: u32,
     texture: Texture
@@ -31,14 +53,55 @@ pub struct SpriteSheet {
 
 impl SpriteSheet {
 
+    /// Also loads the frames and animations from the metadata file next to
+    /// the texture, if there is one.
     pub fn new(factory: &mut Factory, path: &Path, tile_size: u32) -> Result<Self, Box<Error>> {
-        let texture = Texture::new(factory, path)?;
+        let sheet = Self::from_texture(Texture::new(factory, path)?, tile_size);
+        let metadata = metadata_path(path);
+        if metadata.exists() {
+            sheet.with_metadata(&metadata)
+
+        } else {
+            Ok(sheet)
+        }
+    }
+
+    /// Creates a sheet from an already loaded texture, e.g. one which is
+    /// shared between several views.
+    pub fn from_texture(texture: Texture, tile_size: u32) -> Self {
         let size = texture.size();
-        Ok(Self {
+        Self {
             animations: Vec::new(),
+            frames: Vec::new(),
             cols: size.0 / tile_size,
             rows: size.1 / tile_size,
             texture: texture,
+        }
+    }
+
+    /// Replaces all frames and animations with those from a metadata file.
+    pub fn with_metadata(mut self, path: &Path) -> Result<Self, Box<Error>> {
+        let metadata = Metadata::load(path)?;
+        self.frames = metadata.frames()?;
+        self.animations = metadata.animations();
+        Ok(self)
+    }
+
+    /// Returns the index of an atlas frame by its file name, with or
+    /// without extension.
+    pub fn frame_index(&self, name: &str) -> Option<u32> {
+        self.frames.iter().position(|f| is_frame_name(&f.name, name)).map(|i| i as u32)
+    }
+
+    pub fn frame(&self, index: u32) -> Option<&Frame> {
+        self.frames.get(index as usize)
+    }
+
+    /// Returns the animation for the given direction, falling back to the
+    /// animation of the same name without a direction.
+    pub fn animation(&self, name: &str, direction: Option<Direction>) -> Option<&Animation> {
+        self.animations.iter().find(|a| a.name() == name && a.direction() == direction).or_else(|| {
+            self.animations.iter().find(|a| a.name() == name && a.direction().is_none())
         })
     }
 
@@ -46,7 +109,50 @@ impl SpriteSheet {
         &self.texture
     }
 
+    /// Returns the offset from the sprite position and the size of the quad
+    /// which shows the tile on a sprite of the given size.
+    ///
+    /// Grid tiles cover the whole sprite, atlas frames are scaled from their
+    /// original size to the sprite size and moved by their pivot.
+    pub fn quad(&self, index: u32, size: (f32, f32)) -> ((f32, f32), (f32, f32)) {
+        if let Some(frame) = self.frame(index) {
+            let scale = (
+                size.0 / frame.source.0.max(1) as f32,
+                size.1 / frame.source.1.max(1) as f32
+            );
+            (
+                (
+                    frame.offset.0 as f32 * scale.0 - frame.pivot.0 * size.0,
+                    frame.offset.1 as f32 * scale.1 - frame.pivot.1 * size.1
+                ),
+                (frame.rect.2 as f32 * scale.0, frame.rect.3 as f32 * scale.1)
+            )
+
+        } else {
+            ((0.0, 0.0), size)
+        }
+    }
+
+    /// `size` is the number of grid tiles to cover and is ignored for atlas
+    /// frames.
     pub fn uvs(&self, index: u32, size: (f32, f32)) -> [[f32; 2]; 4] {
+
+        if let Some(frame) = self.frame(index) {
+            let (tw, th) = self.texture.size();
+            let (x, y, w, h) = (
+                frame.rect.0 as f32 / tw as f32,
+                frame.rect.1 as f32 / th as f32,
+                frame.rect.2 as f32 / tw as f32,
+                frame.rect.3 as f32 / th as f32
+            );
+            return [
+                [x, y + h],
+                [x + w, y + h],
+                [x, y],
+                [x + w, y]
+            ];
+        }
+
         let (w, h) = (1.0 / self.cols as f32, 1.0 / self.rows as f32);
         let (x, y) = (
             (index % self.cols) as f32 * w,
@@ -58,6 +164,7 @@ impl SpriteSheet {
             [x, y],
             [x + w * size.0, y]
         ]
+
     }
 
 }
@@ -7,6 +7,10 @@
 // except according to those terms.
 
 
+// STD Dependencies -----------------------------------------------------------
+use std::cmp::Ordering;
+
+
 // External Dependencies ------------------------------------------------------
 use renderer::{ColorBuffer, Encoder, Factory, QuadView, Vertex};
 
@@ -16,6 +20,10 @@ use ::animation::Animation;
 use ::sheet::SpriteSheet;
 
 
+// Statics --------------------------------------------------------------------
+const INITIAL_SPRITES: usize = 64;
+
+
 // Sprite Abstraction ---------------------------------------------------------
 #[derive(Debug)]
 pub struct Sprite {
@@ -23,7 +31,12 @@ pub struct Sprite {
     size: (f32, f32),
     tile: u32,
     tile_size: (f32, f32),
-    animation: Option<Animation>
+    layer: i32,
+    sheet: usize,
+    color: [f32; 4],
+    scale: (f32, f32),
+    rotation: f32,
+    animation: Option<(Animation, u64)>
 }
 
 impl Sprite {
@@ -34,6 +47,11 @@ impl Sprite {
             size: (0.0, 0.0),
             tile: 0,
             tile_size: (1.0, 1.0),
+            layer: 0,
+            sheet: 0,
+            color: [1.0; 4],
+            scale: (1.0, 1.0),
+            rotation: 0.0,
             animation: None
         }
     }
@@ -46,6 +64,10 @@ impl Sprite {
         self.position
     }
 
+    pub fn layer(&self) -> i32 {
+        self.layer
+    }
+
     pub fn set_size(&mut self, w: f32, h: f32) {
         self.size.0 = w;
         self.size.1 = h;
@@ -64,6 +86,70 @@ impl Sprite {
         self.tile_size = (x as f32, y as f32);
     }
 
+    /// Sprites on higher layers are drawn on top of those on lower ones,
+    /// within a layer sprites further down the screen are drawn last.
+    pub fn set_layer(&mut self, layer: i32) {
+        self.layer = layer;
+    }
+
+    /// Selects one of the sheets which were added to the view, `0` is the
+    /// sheet the view was created with.
+    pub fn set_sheet(&mut self, index: usize) {
+        self.sheet = index;
+    }
+
+    /// Multiplies the texture colors, `1.0` keeps them unchanged.
+    pub fn set_tint(&mut self, r: f32, g: f32, b: f32) {
+        self.color[0] = r;
+        self.color[1] = g;
+        self.color[2] = b;
+    }
+
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.color[3] = alpha;
+    }
+
+    /// Scales the sprite around its center.
+    pub fn set_scale(&mut self, x: f32, y: f32) {
+        self.scale = (x, y);
+    }
+
+    /// Rotates the sprite clockwise around its center, in radians.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    /// Starts playing an animation at the given render time.
+    ///
+    /// Playing the animation which is already running keeps its progress,
+    /// switching only the direction continues from the same point in time.
+    pub fn play(&mut self, animation: &Animation, time: u64) {
+        let start = match self.animation {
+            Some((ref current, start)) if current.name() == animation.name() => start,
+            _ => time
+        };
+        self.animation = Some((animation.clone(), start));
+        self.animate(time);
+    }
+
+    pub fn stop(&mut self) {
+        self.animation = None;
+    }
+
+    /// Shows the frame of the current animation at the given render time.
+    pub fn animate(&mut self, time: u64) {
+        self.tile = self.tile_at(time);
+    }
+
+    /// Whether an animation is playing which has not yet finished at the
+    /// given render time.
+    pub fn is_playing(&self, time: u64) -> bool {
+        self.animation.as_ref().map(|&(ref animation, start)| {
+            !animation.is_finished(time.saturating_sub(start))
+
+        }).unwrap_or(false)
+    }
+
     pub fn hit(&self, x: f32, y: f32) -> bool {
         x >= self.position.0
         && y >= self.position.1
@@ -71,19 +157,45 @@ impl Sprite {
         && y < self.position.1 + self.size.1
     }
 
+    fn tile_at(&self, time: u64) -> u32 {
+        match self.animation {
+            Some((ref animation, start)) => animation.frame_at(time.saturating_sub(start)),
+            None => self.tile
+        }
+    }
+
 }
 
 
 // SpriteView Implementation --------------------------------------------------
 #[derive(Debug)]
+struct QueuedSprite {
+    layer: i32,
+    depth: f32,
+    sheet: usize,
+    corners: [(f32, f32); 4],
+    uvs: [[f32; 2]; 4],
+    color: [f32; 4]
+}
+
+/// Draws sprites from any number of sheets.
+///
+/// Sprites are queued by `draw_sprite` and sorted by their layer and the
+/// bottom edge of their quad before being uploaded, so callers can draw
+/// them in any order. Consecutive sprites of the same sheet are drawn in a
+/// single batch and the vertex buffer grows whenever more sprites are
+/// queued than it can hold.
+///
+/// Sprites with an animation are drawn with the frame of the render time
+/// passed to `set_time`.
+#[derive(Debug)]
 pub struct SpriteView {
-    sheet: SpriteSheet,
-    sprite_draw_index: usize,
-    sprite_max: usize,
+    sheets: Vec<SpriteSheet>,
+    queue: Vec<QueuedSprite>,
+    time: u64,
     quad_view: QuadView,
     view_width: f32,
-    view_height: f32,
-    dirty: bool,
+    view_height: f32
 }
 
 impl SpriteView {
@@ -93,112 +205,170 @@ impl SpriteView {
         color: ColorBuffer,
         view_width: u32,
         view_height: u32,
-        sheet: SpriteSheet,
-        max_sprites: usize
+        sheet: SpriteSheet
 
     ) -> Self {
 
-        let mut vertices = Vec::with_capacity(max_sprites);
-        for _ in 0..max_sprites {
-
-            let uvs = sheet.uvs(0, (1.0, 1.0));
-
-            // Top left
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[0] });
-
-            // Top right
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[1] });
-
-            // Bottom left
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[2] });
-
-            // Top right
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[1] });
-
-            // bottom right
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[3] });
-
-            // Top bottom left
-            vertices.push(Vertex { pos: [-100000.0, -100000.0], uv: uvs[2] });
-
-        }
-
         let quad_view = QuadView::new(
             factory,
             color,
             view_width,
             view_height,
             sheet.texture().bind(),
-            vertices
+            vec![Vertex { pos: [0.0, 0.0], uv: [0.0, 0.0], color: [1.0; 4] }; INITIAL_SPRITES * 6]
         );
 
         Self {
-            sheet: sheet,
-            sprite_draw_index: 0,
-            sprite_max: max_sprites,
+            sheets: vec![sheet],
+            queue: Vec::with_capacity(INITIAL_SPRITES),
+            time: 0,
             quad_view: quad_view,
             view_width: view_width as f32,
-            view_height: view_height as f32,
-            dirty: true
+            view_height: view_height as f32
         }
     }
 
-    pub fn scroll_to(&mut self, scroll_x: i32, scroll_y: i32) {
-        self.quad_view.scroll_to(-scroll_x as f32, scroll_y as f32);
+    /// Adds another sheet and returns the index by which sprites refer to
+    /// it.
+    pub fn add_sheet(&mut self, sheet: SpriteSheet) -> usize {
+        self.sheets.push(sheet);
+        self.sheets.len() - 1
     }
 
-    pub fn draw(&mut self, encoder: &mut Encoder) {
+    /// Replaces the sheet the view was created with.
+    pub fn set_sheet(&mut self, sheet: SpriteSheet) {
+        self.replace_sheet(0, sheet);
+    }
 
-        if self.dirty {
-            self.dirty = false;
-            self.quad_view.set_dirty();
+    /// Replaces a sheet, sprites drawn afterwards use the new texture.
+    pub fn replace_sheet(&mut self, index: usize, sheet: SpriteSheet) {
+        if let Some(s) = self.sheets.get_mut(index) {
+            *s = sheet;
         }
+    }
 
-        self.quad_view.draw(encoder, Some(self.sprite_draw_index as u32 * 6));
-        self.sprite_draw_index = 0;
+    /// Sets the render time for animated sprites, usually the time which is
+    /// passed to `Renderable::draw`.
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
 
+    pub fn scroll_to(&mut self, scroll_x: i32, scroll_y: i32) {
+        self.quad_view.scroll_to(-scroll_x as f32, scroll_y as f32);
     }
 
-    pub fn draw_sprite(&mut self, sprite: &Sprite) {
+    pub fn draw(&mut self, factory: &mut Factory, encoder: &mut Encoder) {
 
-        if self.sprite_draw_index == self.sprite_max {
+        if self.queue.is_empty() {
             return;
         }
 
-        let vertices = self.quad_view.vertices_mut(self.sprite_draw_index * 6);
-        let uvs = self.sheet.uvs(sprite.tile, sprite.tile_size);
-        let (w, h) = sprite.size;
-        let (x, y) = (
-            (sprite.position.0 - self.view_width / 2.0),
-            -(sprite.position.1 - self.view_height / 2.0 + h)
-        );
+        // Stable, so sprites of the same depth keep the order they were drawn in
+        self.queue.sort_by(|a, b| {
+            a.layer.cmp(&b.layer).then(a.depth.partial_cmp(&b.depth).unwrap_or(Ordering::Equal))
+        });
 
-        // Top left
-        vertices[0].pos = [x, y];
-        vertices[0].uv = uvs[0];
+        let required = self.queue.len() * 6;
+        if required > self.quad_view.vertex_count() {
+            self.quad_view.resize(factory, required.next_power_of_two());
+        }
 
-        // Top right
-        vertices[1].pos = [x + w, y];
-        vertices[1].uv = uvs[1];
+        // Screen space to view space
+        let (vw, vh) = (self.view_width / 2.0, self.view_height / 2.0);
+        let to_view = |(x, y): (f32, f32)| [x - vw, -(y - vh)];
 
-        // Bottom left
-        vertices[2].pos = [x, y + h];
-        vertices[2].uv = uvs[2];
+        for (index, sprite) in self.queue.iter().enumerate() {
 
-        // Top right
-        vertices[3].pos = [x + w, y];
-        vertices[3].uv = uvs[1];
+            let vertices = self.quad_view.vertices_mut(index * 6);
+            let c = sprite.corners;
+            let pos = [to_view(c[0]), to_view(c[1]), to_view(c[2]), to_view(c[3])];
+            let uvs = sprite.uvs;
 
-        // bottom right
-        vertices[4].pos = [x + w, y + h];
-        vertices[4].uv = uvs[3];
+            // Top left
+            vertices[0].pos = pos[0];
+            vertices[0].uv = uvs[0];
+
+            // Top right
+            vertices[1].pos = pos[1];
+            vertices[1].uv = uvs[1];
 
-        // Top bottom left
-        vertices[5].pos = [x, y + h];
-        vertices[5].uv = uvs[2];
+            // Bottom left
+            vertices[2].pos = pos[2];
+            vertices[2].uv = uvs[2];
+
+            // Top right
+            vertices[3].pos = pos[1];
+            vertices[3].uv = uvs[1];
+
+            // bottom right
+            vertices[4].pos = pos[3];
+            vertices[4].uv = uvs[3];
+
+            // Top bottom left
+            vertices[5].pos = pos[2];
+            vertices[5].uv = uvs[2];
+
+            for v in vertices.iter_mut() {
+                v.color = sprite.color;
+            }
+
+        }
+
+        self.quad_view.set_dirty_range(0, required);
+
+        // One batch per run of sprites from the same sheet
+        let mut start = 0;
+        while start < self.queue.len() {
+            let sheet = self.queue[start].sheet;
+            let end = self.queue[start..].iter().position(|s| s.sheet != sheet).map(|i| {
+                start + i
+
+            }).unwrap_or(self.queue.len());
+
+            self.quad_view.set_texture(self.sheets[sheet].texture().bind());
+            self.quad_view.draw_range(encoder, start as u32 * 6, end as u32 * 6);
+            start = end;
+        }
+
+        self.queue.clear();
+
+    }
+
+    /// Queues a sprite for the next call to `draw`, sprites referring to an
+    /// unknown sheet are ignored.
+    pub fn draw_sprite(&mut self, sprite: &Sprite) {
 
-        self.sprite_draw_index += 1;
-        self.dirty = true;
+        let sheet = match self.sheets.get(sprite.sheet) {
+            Some(sheet) => sheet,
+            None => return
+        };
+
+        let tile = sprite.tile_at(self.time);
+        let ((ox, oy), (w, h)) = sheet.quad(tile, sprite.size);
+        let (x, y) = (sprite.position.0 + ox, sprite.position.1 + oy);
+
+        // Scaled and rotated around the center of the sprite
+        let (cx, cy) = (sprite.position.0 + sprite.size.0 * 0.5, sprite.position.1 + sprite.size.1 * 0.5);
+        let (sx, sy) = sprite.scale;
+        let (sin, cos) = sprite.rotation.sin_cos();
+        let transform = |px: f32, py: f32| {
+            let (dx, dy) = ((px - cx) * sx, (py - cy) * sy);
+            (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+        };
+
+        self.queue.push(QueuedSprite {
+            layer: sprite.layer,
+            depth: y + h,
+            sheet: sprite.sheet,
+            corners: [
+                transform(x, y + h),
+                transform(x + w, y + h),
+                transform(x, y),
+                transform(x + w, y)
+            ],
+            uvs: sheet.uvs(tile, sprite.tile_size),
+            color: sprite.color
+        });
 
     }
 
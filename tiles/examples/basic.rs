@@ -13,11 +13,9 @@ extern crate audio;
 extern crate tiles;
 extern crate sprites;
 extern crate renderer;
-extern crate pathfinding;
 
 
 // STD Dependencies -----------------------------------------------------------
-use std::iter;
 use std::time::Instant;
 use std::path::{Path, PathBuf};
 
@@ -26,16 +24,16 @@ use std::path::{Path, PathBuf};
 use rand::Rng;
 use audio::AudioQueue;
 use sprites::{SpriteSheet, SpriteView, Sprite};
-use tiles::{TileData, TileSource, TileGrid, TerrainGrid, TileSet, TileType};
-use renderer::{Key, Keyboard, Button, Mouse, Renderable, Encoder};
-use pathfinding::astar;
+use tiles::{GridCell, NavData, TileData, TileSource, TileGrid, TerrainGrid, TileSet};
+use renderer::{Key, Keyboard, Button, Mouse, Renderable, Encoder, Factory};
 
 
 // Example --------------------------------------------------------------------
 struct Map {
     terrain_grid: TerrainGrid,
-    col_grid: TileGrid<CollisionData>,
+    col_grid: TileGrid<NavData>,
     sprite_view: SpriteView,
+    factory: Factory,
     audio: AudioQueue,
     scroll: (i32, i32),
     units: Vec<Unit>
@@ -49,7 +47,7 @@ impl Renderable for Map {
         }
     }
 
-    fn draw(&mut self, time: u64, mut encoder: &mut Encoder, keyboard: &Keyboard, mouse: &Mouse) where Self: Sized {
+    fn draw(&mut self, time: u64, _: f32, mut encoder: &mut Encoder, keyboard: &Keyboard, mouse: &Mouse) where Self: Sized {
 
         // Scrolling
         if keyboard.is_pressed(Key::A) {
@@ -131,6 +129,7 @@ impl Renderable for Map {
 
 
         // Draw test map
+        self.terrain_grid.animate(time);
         self.terrain_grid.draw(&mut encoder);
         //self.col_grid.draw(&mut encoder);
 
@@ -138,7 +137,7 @@ impl Renderable for Map {
             unit.draw(time, &mut self.sprite_view);
         }
 
-        self.sprite_view.draw(&mut encoder);
+        self.sprite_view.draw(&mut self.factory, &mut encoder);
 
     }
 
@@ -245,7 +244,7 @@ impl Unit {
         self.sprite.hit(x, y)
     }
 
-    pub fn move_to(&mut self, collision: &CollisionData, x: i32, y: i32) {
+    pub fn move_to(&mut self, nav: &NavData, x: i32, y: i32) {
 
         let goal = GridCell(x, y);
         let (sx, sy) = self.sprite.position();
@@ -254,21 +253,11 @@ impl Unit {
         println!("[Pathfinding] Searching...");
 
         let start = Instant::now();
-        let result = astar(&begin, |c| {
-            collision.neighbors(c.0, c.1)
-
-        }, |c| {
-            // TODO use sqrt instead of manhatten?
-            ((c.0 - goal.0).abs() + (c.1 - goal.1).abs()) as usize
-
-        }, |c| {
-            *c == goal
-        });
+        let result = nav.find_path(begin, goal, |_, _| false);
 
         println!("[Pathfinding] Completed in 0.{:?}ms", start.elapsed().subsec_nanos() / 100000);
-        if let Some(result) = result {
-            self.path = result.0.into_iter().skip(1).take_while(|c| collision.is_traversable(c.0, c.1)).collect();
-            self.path.reverse();
+        if let Some(path) = result {
+            self.path = path;
             println!("[Pathfinding] Path with length {} created", self.path.len());
         }
 
@@ -317,131 +306,6 @@ impl Unit {
 }
 
 
-// Collision Structs ----------------------------------------------------------
-
-// TODO clean up and integrate into tiles
-
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub struct GridCell(i32, i32);
-
-
-pub struct CollisionData {
-    pub width: u32,
-    pub height: u32,
-    pub indices: Vec<u32>
-}
-
-impl CollisionData {
-
-    pub fn new(width: u32, height: u32) -> Self {
-        Self {
-            width: width,
-            height: height,
-            indices: iter::repeat(1).take((width * height) as usize).collect()
-        }
-    }
-
-    pub fn initialize(&mut self, data: &TileData, tileset: &TileSet) {
-        for (i, d) in self.indices.iter_mut().zip(data.indices().iter()) {
-            *i = match tileset.typ(*d) {
-                TileType::Ground => 0,
-                TileType::Water => 4,
-                TileType::Other => 2
-            };
-        }
-    }
-
-    pub fn neighbors(&self, x: i32, y: i32) -> Vec<(GridCell, usize)> {
-        let mut cells = Vec::new();
-        for px in x - 1..x + 2 {
-            for py in y - 1..y + 2 {
-                if px != x || py != y {
-                    if self.is_traversable(px, py) {
-                        cells.push((GridCell(px, py), self.distance(x, y, px, py)));
-
-                    } else {
-                        cells.push((GridCell(px, py), 4096));
-                    }
-                }
-            }
-        }
-        cells
-    }
-
-    pub fn distance(&self, x: i32, y: i32, tx: i32, ty: i32) -> usize {
-        if (tx == x && ty != y) || (ty == y && tx != x) {
-            1
-
-        } else {
-            2
-        }
-    }
-
-    pub fn is_traversable(&self, x: i32, y: i32) -> bool {
-        if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
-            let i = x + y * self.width as i32;
-            self.indices[i as usize] == 0
-
-        } else {
-            false
-        }
-    }
-
-}
-
-impl TileSource for CollisionData {
-
-    fn width(&self) -> u32 {
-        self.width
-    }
-
-    fn height(&self) -> u32 {
-        self.height
-    }
-
-    fn index(&self, index: usize) -> u32 {
-        self.indices[index]
-    }
-
-    fn indices(&self) -> &[u32] {
-        &self.indices
-    }
-
-    fn set_tile_index(&mut self, x: i32, y: i32, index: u32) -> bool {
-        let i = y * self.width as i32 + x;
-        if i >= 0 && i < (self.width * self.height) as i32 {
-            self.indices[i as usize] = index;
-            true
-
-        } else {
-            false
-        }
-    }
-
-    fn get_tile_index(&self, x: i32, y: i32) -> Option<u32> {
-        let w = self.width as i32;
-        let h = self.height as i32;
-        if x >= 0 && x < w && y >= 0 && y < h {
-            Some(self.indices[(y * w + x) as usize])
-
-        } else {
-            None
-        }
-    }
-
-}
-
-impl Default for CollisionData {
-    fn default() -> Self {
-        Self {
-            width: 0,
-            height: 0,
-            indices: Vec::new()
-        }
-    }
-}
-
-
 // Demo -----------------------------------------------------------------------
 fn main() {
     renderer::run::<Map, _>("Map", 640, 480, 60, 10, |mut target| {
@@ -450,7 +314,7 @@ fn main() {
         let ts = TileSet::new(&mut target.factory, Path::new("../assets/maps/develop.tsx")).unwrap();
         let mut terrain_grid = TerrainGrid::new(&mut target.factory, target.color.clone(), target.width, target.height, 32, ts);
 
-        let m = TileData::new(Path::new("../assets/maps/develop.tmx"));
+        let m = TileData::new(Path::new("../assets/maps/develop.tmx")).expect("Failed to load map.");
         terrain_grid.set_source(m);
 
         // Collision
@@ -459,7 +323,7 @@ fn main() {
 
         let c = {
             let s = terrain_grid.source();
-            CollisionData::new(s.width(), s.height())
+            NavData::new(s.width(), s.height())
         };
         col_grid.set_source(c);
 
@@ -475,8 +339,7 @@ fn main() {
             target.color.clone(),
             target.width,
             target.height,
-            sprite_sheet,
-            128
+            sprite_sheet
         );
 
         let units = vec![
@@ -488,6 +351,7 @@ fn main() {
             terrain_grid: terrain_grid,
             col_grid: col_grid,
             sprite_view: sprite_view,
+            factory: target.factory,
             audio: AudioQueue::new(),
             scroll: (0, 0),
             units: units
@@ -8,39 +8,251 @@
 
 
 // STD Dependencies -----------------------------------------------------------
+use std::io;
+use std::fmt;
+use std::mem;
+use std::error::Error;
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 
 
 // External Dependencies ------------------------------------------------------
+use base64;
+use flate2::read::{GzDecoder, ZlibDecoder};
 use serde_xml_rs::deserialize;
 
 
 // Internal Dependencies ------------------------------------------------------
+use ::nav::GridCell;
+use ::objects::{MapObject, MapObjects, MapProperty, ObjectDetails, ResourceSpawn, TriggerArea};
 use ::source::TileSource;
 
 
-// Tiledata Abstraction -------------------------------------------------------
+// Statics --------------------------------------------------------------------
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL: u32 = 0x1000_0000;
+
+
+// Map Errors -----------------------------------------------------------------
+
+/// Enumeration of all possible errors when loading a map.
 #[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    Xml(String),
+    InvalidNumber(String),
+    NoLayers,
+    UnsupportedEncoding(String),
+    UnsupportedCompression(String),
+    /// The data of the named layer could not be decoded or has the wrong size.
+    InvalidData(String),
+    /// The named layer contains a global tile ID without a tileset.
+    InvalidTile(String, u32),
+    /// The named base layer uses tiles from another than the first tileset.
+    MixedTilesets(String),
+    /// The tileset starting at the given global ID is embedded into the map
+    /// instead of referring to a `.tsx` file.
+    EmbeddedTileset(u32),
+    /// A generated map has start locations which cannot reach each other.
+    Unreachable,
+    /// More players than the given number of start locations were requested.
+    TooManyPlayers(usize, usize)
+}
+
+impl From<io::Error> for MapError {
+    fn from(error: io::Error) -> MapError {
+        MapError::Io(error)
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MapError::Io(ref err) => write!(f, "I/O error: {}", err),
+            MapError::Xml(ref err) => write!(f, "XML error: {}", err),
+            MapError::InvalidNumber(ref value) => write!(f, "invalid number \"{}\"", value),
+            MapError::NoLayers => write!(f, "map has no tile layers"),
+            MapError::UnsupportedEncoding(ref encoding) => write!(f, "unsupported layer encoding \"{}\"", encoding),
+            MapError::UnsupportedCompression(ref compression) => write!(f, "unsupported layer compression \"{}\"", compression),
+            MapError::InvalidData(ref layer) => write!(f, "invalid data in layer \"{}\"", layer),
+            MapError::InvalidTile(ref layer, gid) => write!(f, "tile {} in layer \"{}\" belongs to no tileset", gid, layer),
+            MapError::MixedTilesets(ref layer) => write!(f, "base layer \"{}\" uses tiles from more than the first tileset", layer),
+            MapError::EmbeddedTileset(gid) => write!(f, "tileset with first gid {} is embedded, only external .tsx tilesets are supported", gid),
+            MapError::Unreachable => write!(f, "start locations are not connected"),
+            MapError::TooManyPlayers(players, starts) => write!(f, "{} players requested but only {} start locations are supported", players, starts)
+        }
+    }
+}
+
+impl Error for MapError {
+    fn description(&self) -> &str {
+        "failed to load map"
+    }
+}
+
+
+// Tilesets -------------------------------------------------------------------
+
+/// A tileset referenced by a map, its tiles start at global ID `first_gid`.
+#[derive(Debug, Clone)]
+pub struct MapTileset {
+    pub first_gid: u32,
+    /// Path of the `.tsx` file relative to the map.
+    pub source: String
+}
+
+
+// Tile Flips -----------------------------------------------------------------
+
+/// Flip flags of a single tile as stored in the high bits of its global ID.
+///
+/// Tiled applies the diagonal flip first, rotating a tile by 90 degrees
+/// combines a diagonal flip with a horizontal or vertical one.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TileFlip {
+    pub horizontal: bool,
+    pub vertical: bool,
+    pub diagonal: bool
+}
+
+impl TileFlip {
+
+    /// Splits a global tile ID into the plain ID and its flip flags.
+    pub fn from_gid(gid: u32) -> (u32, TileFlip) {
+        let flip = TileFlip {
+            horizontal: gid & FLIPPED_HORIZONTALLY != 0,
+            vertical: gid & FLIPPED_VERTICALLY != 0,
+            diagonal: gid & FLIPPED_DIAGONALLY != 0
+        };
+        (gid & !(FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL), flip)
+    }
+
+    pub fn to_bits(&self) -> u32 {
+        (if self.horizontal { FLIPPED_HORIZONTALLY } else { 0 })
+            | (if self.vertical { FLIPPED_VERTICALLY } else { 0 })
+            | (if self.diagonal { FLIPPED_DIAGONALLY } else { 0 })
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        self.horizontal || self.vertical || self.diagonal
+    }
+
+}
+
+
+// Tile Layers ----------------------------------------------------------------
+
+/// An additional tile layer which is drawn on top of the base layer, empty
+/// tiles are `None`.
+///
+/// All tiles of a layer belong to the same tileset, their indices are local
+/// to it. Tiled layers with tiles from several tilesets are split into one
+/// layer per tileset, which all share the name of the original layer.
+#[derive(Debug, Clone)]
+pub struct TileLayer {
+    pub name: String,
+    pub tileset: usize,
+    pub indices: Vec<Option<u32>>,
+    pub flips: Vec<TileFlip>
+}
+
+impl TileLayer {
+    pub fn get_tile_index(&self, x: i32, y: i32, width: u32) -> Option<u32> {
+        if x >= 0 && x < width as i32 && y >= 0 {
+            self.indices.get((y * width as i32 + x) as usize).and_then(|i| *i)
+
+        } else {
+            None
+        }
+    }
+}
+
+
+// Tiledata Abstraction -------------------------------------------------------
+
+/// Tile indices of a map.
+///
+/// The first tile layer of the map is the base layer which terrain,
+/// navigation and fog of war operate on, it must only use tiles from the
+/// first tileset and loading fails with `MapError::MixedTilesets` otherwise.
+/// All further tile layers are only decorative and drawn on top of it in
+/// order, they may use any of the tilesets.
+#[derive(Debug, Clone)]
 pub struct TileData {
     pub width: u32,
     pub height: u32,
-    pub indices: Vec<u32>
+    pub tile_size: (u32, u32),
+    pub tilesets: Vec<MapTileset>,
+    /// Name of the base layer in Tiled.
+    pub base_name: String,
+    pub indices: Vec<u32>,
+    pub flips: Vec<TileFlip>,
+    pub layers: Vec<TileLayer>,
+    pub objects: MapObjects,
+    track_changes: bool,
+    changed: Vec<(i32, i32)>
 }
 
 impl TileData {
 
-    pub fn new(path: &Path) -> Self {
+    pub fn new(path: &Path) -> Result<Self, MapError> {
 
-        let file = File::open(path).expect("TileData: Failed to open map file.");
-        let map: Map = deserialize(file).expect("TileData: Failed to parse map file.");
-        let base = &map.layer[0];
+        let file = File::open(path)?;
+        let map: Map = deserialize(file).map_err(|err| MapError::Xml(err.to_string()))?;
 
-        Self {
-            width: base.width.parse().unwrap_or(0),
-            height: base.height.parse().unwrap_or(0),
-            indices: base.data.split(',').map(|i| i.trim().parse::<u32>().unwrap_or(1) - 1).collect()
+        let (width, height) = (parse_number(&map.width)?, parse_number(&map.height)?);
+        let tile_size = (
+            parse_number(&map.tilewidth)?.max(1),
+            parse_number(&map.tileheight)?.max(1)
+        );
+
+        let mut tilesets = Vec::new();
+        for tileset in &map.tileset {
+            let first_gid = parse_number(&tileset.firstgid)?;
+            tilesets.push(MapTileset {
+                first_gid: first_gid,
+                source: tileset.source.clone().ok_or(MapError::EmbeddedTileset(first_gid))?
+            });
         }
+        tilesets.sort_by_key(|t| t.first_gid);
+
+        let mut layers = Vec::new();
+        for layer in &map.layer {
+            let gids = decode_layer(layer)?;
+            if gids.len() != (width * height) as usize {
+                return Err(MapError::InvalidData(layer.name.clone()));
+            }
+            layers.push(resolve_layer(&layer.name, gids, &tilesets)?);
+        }
+
+        if layers.is_empty() {
+            return Err(MapError::NoLayers);
+        }
+
+        let mut base = layers.remove(0);
+        if base.len() > 1 || base[0].tileset != 0 {
+            return Err(MapError::MixedTilesets(base.remove(0).name));
+        }
+
+        let base = base.remove(0);
+        let layers = layers.into_iter().flat_map(|l| l.into_iter()).collect();
+
+        Ok(Self {
+            width: width,
+            height: height,
+            tile_size: tile_size,
+            tilesets: tilesets,
+            base_name: base.name,
+            indices: base.indices.into_iter().map(|i| i.unwrap_or(0)).collect(),
+            flips: base.flips,
+            layers: layers,
+            objects: parse_objects(&map.objectgroup, tile_size),
+            track_changes: false,
+            changed: Vec::new()
+        })
 
     }
 
@@ -82,6 +294,21 @@ impl TileData {
         ]
     }
 
+    /// Enables or disables tracking of changed tiles, tracking is disabled by
+    /// default and disabling it forgets all tiles changed so far.
+    pub fn set_track_changes(&mut self, track: bool) {
+        self.track_changes = track;
+        if !track {
+            self.changed.clear();
+        }
+    }
+
+    /// Returns and forgets all tiles whose index was changed since the last
+    /// call, only tiles changed while tracking is enabled are returned.
+    pub fn take_changed(&mut self) -> Vec<(i32, i32)> {
+        mem::replace(&mut self.changed, Vec::new())
+    }
+
     fn limit_offset(&self, x: i32, y: i32) -> Option<(i32, i32)> {
         let w = self.width as i32;
         let h = self.height as i32;
@@ -113,10 +340,44 @@ impl TileSource for TileData {
         &self.indices
     }
 
+    fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+
+    fn flip(&self, index: usize) -> TileFlip {
+        self.flips.get(index).cloned().unwrap_or_default()
+    }
+
     fn set_tile_index(&mut self, x: i32, y: i32, index: u32) -> bool {
         let i = y * self.width as i32 + x;
         if i >= 0 && i < (self.width * self.height) as i32 {
-            self.indices[i as usize] = index;
+            if self.indices[i as usize] != index {
+                self.indices[i as usize] = index;
+                if let Some(flip) = self.flips.get_mut(i as usize) {
+                    *flip = TileFlip::default();
+                }
+                if self.track_changes {
+                    self.changed.push((x, y));
+                }
+            }
+            true
+
+        } else {
+            false
+        }
+    }
+
+    fn set_tile_flip(&mut self, x: i32, y: i32, flip: TileFlip) -> bool {
+        let i = y * self.width as i32 + x;
+        if i >= 0 && i < (self.width * self.height) as i32 {
+            if let Some(current) = self.flips.get_mut(i as usize) {
+                if *current != flip {
+                    *current = flip;
+                    if self.track_changes {
+                        self.changed.push((x, y));
+                    }
+                }
+            }
             true
 
         } else {
@@ -142,20 +403,277 @@ impl Default for TileData {
         Self {
             width: 0,
             height: 0,
-            indices: Vec::new()
+            tile_size: (1, 1),
+            tilesets: Vec::new(),
+            base_name: "Ground".to_string(),
+            indices: Vec::new(),
+            flips: Vec::new(),
+            layers: Vec::new(),
+            objects: MapObjects::default(),
+            track_changes: false,
+            changed: Vec::new()
+        }
+    }
+}
+
+
+// Helpers --------------------------------------------------------------------
+
+fn parse_number(value: &str) -> Result<u32, MapError> {
+    value.trim().parse().map_err(|_| MapError::InvalidNumber(value.to_string()))
+}
+
+/// Decodes the global tile IDs of a layer from CSV or base64, the latter
+/// either uncompressed or compressed with zlib or gzip.
+fn decode_layer(layer: &Layer) -> Result<Vec<u32>, MapError> {
+
+    let data = &layer.data;
+    let invalid = || MapError::InvalidData(layer.name.clone());
+
+    match data.encoding.as_ref().map(|e| e.as_str()) {
+        Some("csv") => {
+            data.value.split(',').map(|i| i.trim()).filter(|i| !i.is_empty()).map(|i| {
+                i.parse::<u32>().map_err(|_| invalid())
+
+            }).collect()
+        },
+        Some("base64") => {
+
+            let text: String = data.value.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::decode(&text).map_err(|_| invalid())?;
+            let bytes = match data.compression.as_ref().map(|c| c.as_str()) {
+                None => bytes,
+                Some("zlib") => read_all(ZlibDecoder::new(&bytes[..]))?,
+                Some("gzip") => read_all(GzDecoder::new(&bytes[..]))?,
+                Some(compression) => return Err(MapError::UnsupportedCompression(compression.to_string()))
+            };
+
+            // Global IDs are stored as little endian 32-bit integers
+            if bytes.len() % 4 != 0 {
+                return Err(invalid());
+            }
+
+            Ok(bytes.chunks(4).map(|b| {
+                b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24
+
+            }).collect())
+
+        },
+        Some(encoding) => Err(MapError::UnsupportedEncoding(encoding.to_string())),
+        None => Err(MapError::UnsupportedEncoding("xml".to_string()))
+    }
+
+}
+
+fn read_all<R: Read>(mut reader: R) -> Result<Vec<u8>, MapError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Converts global tile IDs into indices local to the tilesets, a global ID
+/// of `0` marks an empty tile.
+///
+/// Returns one layer per used tileset in the order of the tilesets, layers
+/// without any tiles use the first tileset.
+fn resolve_layer(name: &str, gids: Vec<u32>, tilesets: &[MapTileset]) -> Result<Vec<TileLayer>, MapError> {
+
+    let count = gids.len();
+    let mut layers: Vec<TileLayer> = Vec::new();
+    for (i, gid) in gids.into_iter().enumerate() {
+
+        let (gid, flip) = TileFlip::from_gid(gid);
+        if gid == 0 {
+            continue;
+        }
+
+        let (set, index) = tilesets.iter().enumerate().rev().find(|&(_, t)| {
+            t.first_gid > 0 && gid >= t.first_gid
+
+        }).map(|(i, t)| (i, gid - t.first_gid)).ok_or_else(|| {
+            MapError::InvalidTile(name.to_string(), gid)
+        })?;
+
+        let l = match layers.iter().position(|l| l.tileset == set) {
+            Some(l) => l,
+            None => {
+                layers.push(empty_layer(name, set, count));
+                layers.len() - 1
+            }
+        };
+
+        layers[l].indices[i] = Some(index);
+        layers[l].flips[i] = flip;
+
+    }
+
+    if layers.is_empty() {
+        layers.push(empty_layer(name, 0, count));
+    }
+
+    layers.sort_by_key(|l| l.tileset);
+    Ok(layers)
+
+}
+
+fn empty_layer(name: &str, tileset: usize, count: usize) -> TileLayer {
+    TileLayer {
+        name: name.to_string(),
+        tileset: tileset,
+        indices: vec![None; count],
+        flips: vec![TileFlip::default(); count]
+    }
+}
+
+fn parse_objects(groups: &[ObjectGroup], tile_size: (u32, u32)) -> MapObjects {
+
+    let mut objects: Vec<&Object> = groups.iter().flat_map(|g| g.object.iter()).collect();
+    objects.sort_by_key(|o| o.id.parse::<u32>().unwrap_or(0));
+
+    let mut parsed = MapObjects::default();
+    for object in objects {
+
+        let (x, y) = (
+            object.x.parse::<f32>().unwrap_or(0.0) as i32,
+            object.y.parse::<f32>().unwrap_or(0.0) as i32
+        );
+
+        let (w, h) = (
+            object.width.as_ref().and_then(|w| w.parse::<f32>().ok()).unwrap_or(0.0) as u32,
+            object.height.as_ref().and_then(|h| h.parse::<f32>().ok()).unwrap_or(0.0) as u32
+        );
+
+        let cell = GridCell(x / tile_size.0 as i32, y / tile_size.1 as i32);
+        let property = |name: &str| {
+            object.properties.property.iter().find(|p| p.name == name).map(|p| p.value.to_string())
+        };
+
+        // Properties which are not interpreted by the game are kept as is
+        let properties = |known: &[&str]| -> Vec<MapProperty> {
+            object.properties.property.iter().filter(|p| !known.contains(&p.name.as_str())).map(|p| {
+                MapProperty {
+                    name: p.name.to_string(),
+                    typ: p.typ.clone(),
+                    value: p.value.to_string()
+                }
+
+            }).collect()
+        };
+
+        // Tiled 1.9 renamed the object type to class
+        let typ = object.typ.as_ref().or_else(|| object.class.as_ref());
+        match typ.map(|t| t.as_str()) {
+            Some("start") => {
+                parsed.starts.push(cell);
+                if !object.name.is_empty() || !object.properties.property.is_empty() {
+                    parsed.start_details.insert(cell, ObjectDetails {
+                        name: object.name.to_string(),
+                        properties: properties(&[])
+                    });
+                }
+            },
+            Some("resource") => parsed.resources.push(ResourceSpawn {
+                cell: cell,
+                resource: property("resource").unwrap_or_else(|| object.name.to_string()),
+                amount: property("amount").and_then(|a| a.parse().ok()),
+                name: object.name.to_string(),
+                properties: properties(&["resource", "amount"])
+            }),
+            Some("trigger") => parsed.triggers.push(TriggerArea {
+                name: object.name.to_string(),
+                origin: cell,
+                size: (
+                    ((w + tile_size.0 - 1) / tile_size.0).max(1),
+                    ((h + tile_size.1 - 1) / tile_size.1).max(1)
+                ),
+                properties: properties(&[])
+            }),
+            _ => parsed.other.push(MapObject {
+                name: object.name.to_string(),
+                typ: typ.cloned(),
+                x: object.x.parse().unwrap_or(0.0),
+                y: object.y.parse().unwrap_or(0.0),
+                width: object.width.as_ref().and_then(|w| w.parse().ok()),
+                height: object.height.as_ref().and_then(|h| h.parse().ok()),
+                properties: properties(&[])
+            })
         }
+
     }
+
+    parsed
+
+}
+
+
+// Tiled XML Format -----------------------------------------------------------
+#[derive(Debug, Deserialize)]
+struct Tileset {
+    firstgid: String,
+    source: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
 struct Layer {
+    #[serde(default)]
+    name: String,
+    data: Data
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(rename = "$value", default)]
+    value: String
+}
+
+#[derive(Debug, Deserialize)]
+struct Map {
     width: String,
     height: String,
-    data: String
+    tilewidth: String,
+    tileheight: String,
+    #[serde(default)]
+    tileset: Vec<Tileset>,
+    #[serde(default)]
+    layer: Vec<Layer>,
+    #[serde(default)]
+    objectgroup: Vec<ObjectGroup>
 }
 
 #[derive(Debug, Deserialize)]
-struct Map {
-    layer: Vec<Layer>
+struct ObjectGroup {
+    #[serde(default)]
+    object: Vec<Object>
+}
+
+#[derive(Debug, Deserialize)]
+struct Object {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename(deserialize="type"))]
+    typ: Option<String>,
+    class: Option<String>,
+    x: String,
+    y: String,
+    width: Option<String>,
+    height: Option<String>,
+    #[serde(default)]
+    properties: Properties
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Properties {
+    property: Vec<Property>
+}
+
+#[derive(Debug, Deserialize)]
+struct Property {
+    name: String,
+    #[serde(rename(deserialize="type"))]
+    typ: Option<String>,
+    value: String
 }
 
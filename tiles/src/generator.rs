@@ -0,0 +1,391 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::collections::VecDeque;
+
+
+// External Dependencies ------------------------------------------------------
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::data::{MapError, MapTileset, TileData, TileFlip};
+use ::nav::{GridCell, NavData};
+use ::source::TileSource;
+use ::tileset::TileSet;
+
+
+// Statics --------------------------------------------------------------------
+const START_INSET: i32 = 8;
+const START_CLEARING: i32 = 4;
+const RESOURCE_OFFSET: i32 = 5;
+const CORRIDOR_WIDTH: i32 = 2;
+const MAX_STARTS: usize = 4;
+
+
+// Generator Options ----------------------------------------------------------
+
+/// Settings for `TileData::generate`.
+///
+/// The terrain names and ground tiles default to the ones of `develop.tsx`.
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Number of start locations, two players get a point symmetric map,
+    /// more players a map which is mirrored along both axes. Maps are
+    /// generated for at most four players, one start location per corner.
+    pub players: usize,
+    /// Source of the tileset written into saved maps.
+    pub tileset: String,
+    pub tile_size: (u32, u32),
+    /// Plain ground tiles which are picked at random.
+    pub ground: Vec<u32>,
+    /// Terrain names and the fraction of the noise range they cover, later
+    /// terrains are placed on top of earlier ones.
+    pub terrains: Vec<(String, f32)>
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            width: 64,
+            height: 64,
+            players: 2,
+            tileset: "develop.tsx".to_string(),
+            tile_size: (16, 16),
+            ground: vec![0, 1, 19],
+            terrains: vec![
+                ("Water".to_string(), 0.3),
+                ("Forest".to_string(), 0.35),
+                ("Rocks".to_string(), 0.28)
+            ]
+        }
+    }
+}
+
+
+// Map Generator --------------------------------------------------------------
+impl TileData {
+
+    /// Generates a random map from `seed`, the same seed and options always
+    /// result in the same map.
+    ///
+    /// Every start location is surrounded by a clearing with a forest and a
+    /// rock patch of the same size nearby. In case the terrain separates the
+    /// start locations, corridors towards the center of the map are cleared.
+    pub fn generate(tileset: &TileSet, seed: [u32; 4], options: &GeneratorOptions) -> Result<Self, MapError> {
+
+        if options.players > MAX_STARTS {
+            return Err(MapError::TooManyPlayers(options.players, MAX_STARTS));
+        }
+
+        let mut rng = XorShiftRng::from_seed([seed[0] | 1, seed[1], seed[2], seed[3]]);
+        let mut generator = Generator::new(options);
+        let starts = generator.starts();
+
+        // Terrain index within options.terrains for every cell, starting
+        // with fresh noise for each terrain
+        for (i, &(_, coverage)) in options.terrains.iter().enumerate() {
+            let noise = Noise::new(&mut rng, options.width, options.height);
+            for y in 0..options.height {
+                for x in 0..options.width {
+                    let (cx, cy) = generator.canonical(x, y);
+                    if noise.get(cx, cy) > 1.0 - coverage {
+                        generator.set(x as i32, y as i32, Some(i));
+                    }
+                }
+            }
+        }
+
+        // Clearings and resources around the start locations
+        let resources: Vec<(usize, i32, i32)> = [("Forest", 3, 3), ("Rocks", 2, 2)].iter().filter_map(|&(name, w, h)| {
+            options.terrains.iter().position(|t| t.0 == name).map(|i| (i, w, h))
+
+        }).collect();
+
+        for start in &starts {
+            let (dx, dy) = generator.direction(*start);
+            generator.fill(*start, -START_CLEARING, -START_CLEARING, START_CLEARING * 2 + 1, START_CLEARING * 2 + 1, None);
+            for (i, &(terrain, w, h)) in resources.iter().enumerate() {
+                let (ox, oy) = if i % 2 == 0 { (RESOURCE_OFFSET, -1) } else { (-1, RESOURCE_OFFSET) };
+                generator.fill(*start, ox * dx, oy * dy, w * dx, h * dy, Some(terrain));
+            }
+        }
+
+        let mut data = generator.build(tileset);
+        if !is_reachable(&data, tileset, &starts) {
+            let center = GridCell(options.width as i32 / 2, options.height as i32 / 2);
+            for start in &starts {
+                generator.clear_corridor(*start, center);
+            }
+            data = generator.build(tileset);
+            if !is_reachable(&data, tileset, &starts) {
+                return Err(MapError::Unreachable);
+            }
+        }
+
+        data.objects.starts = starts;
+        Ok(data)
+
+    }
+
+}
+
+
+// Generator ------------------------------------------------------------------
+struct Generator<'a> {
+    options: &'a GeneratorOptions,
+    cells: Vec<Option<usize>>
+}
+
+impl<'a> Generator<'a> {
+
+    fn new(options: &'a GeneratorOptions) -> Self {
+        Self {
+            options: options,
+            cells: vec![None; (options.width * options.height) as usize]
+        }
+    }
+
+    /// Start locations in the same order as the default ones of the game.
+    fn starts(&self) -> Vec<GridCell> {
+        let (w, h) = (self.options.width as i32, self.options.height as i32);
+        let inset = START_INSET.min(w / 4).min(h / 4);
+        vec![
+            GridCell(inset, inset),
+            GridCell(w - inset - 1, h - inset - 1),
+            GridCell(w - inset - 1, inset),
+            GridCell(inset, h - inset - 1)
+
+        ].into_iter().take(self.options.players.max(2)).collect()
+    }
+
+    /// Returns the cell from which the terrain at `x` and `y` is mirrored.
+    fn canonical(&self, x: u32, y: u32) -> (u32, u32) {
+        let (mx, my) = (self.options.width - 1 - x, self.options.height - 1 - y);
+        if self.options.players > 2 {
+            (x.min(mx), y.min(my))
+
+        } else if (my, mx) < (y, x) {
+            (mx, my)
+
+        } else {
+            (x, y)
+        }
+    }
+
+    /// Direction from the start location towards the center of the map.
+    fn direction(&self, start: GridCell) -> (i32, i32) {
+        (
+            if start.0 < self.options.width as i32 / 2 { 1 } else { -1 },
+            if start.1 < self.options.height as i32 / 2 { 1 } else { -1 }
+        )
+    }
+
+    fn set(&mut self, x: i32, y: i32, terrain: Option<usize>) {
+        if x >= 0 && y >= 0 && x < self.options.width as i32 && y < self.options.height as i32 {
+            self.cells[(x + y * self.options.width as i32) as usize] = terrain;
+        }
+    }
+
+    fn get(&self, x: i32, y: i32) -> Option<Option<usize>> {
+        if x >= 0 && y >= 0 && x < self.options.width as i32 && y < self.options.height as i32 {
+            Some(self.cells[(x + y * self.options.width as i32) as usize])
+
+        } else {
+            None
+        }
+    }
+
+    /// Fills the rectangle at offset `ox` and `oy` from `origin`, negative
+    /// sizes extend the rectangle to the left or top.
+    fn fill(&mut self, origin: GridCell, ox: i32, oy: i32, w: i32, h: i32, terrain: Option<usize>) {
+        for y in 0..h.abs() {
+            for x in 0..w.abs() {
+                self.set(origin.0 + ox + x * w.signum(), origin.1 + oy + y * h.signum(), terrain);
+            }
+        }
+    }
+
+    fn clear_corridor(&mut self, from: GridCell, to: GridCell) {
+        let (mut x, mut y) = (from.0, from.1);
+        while x != to.0 || y != to.1 {
+            x += (to.0 - x).signum();
+            y += (to.1 - y).signum();
+            self.fill(GridCell(x, y), 0, 0, CORRIDOR_WIDTH, CORRIDOR_WIDTH, None);
+        }
+    }
+
+    /// Removes terrain cells which are not part of any 2x2 block.
+    ///
+    /// Only needed for terrains without standalone tiles, all others fall
+    /// back to them during reflow.
+    fn remove_single_cells(&mut self, terrain: usize) {
+        loop {
+
+            let mut removed = 0;
+            for y in 0..self.options.height as i32 {
+                for x in 0..self.options.width as i32 {
+                    if self.get(x, y) == Some(Some(terrain)) && !self.in_block(x, y, terrain) {
+                        self.set(x, y, None);
+                        removed += 1;
+                    }
+                }
+            }
+
+            if removed == 0 {
+                break;
+            }
+
+        }
+    }
+
+    fn in_block(&self, x: i32, y: i32, terrain: usize) -> bool {
+        // Cells outside of the map count as part of every terrain, just
+        // like during reflow
+        let is = |x: i32, y: i32| self.get(x, y).map(|t| t == Some(terrain)).unwrap_or(true);
+        [(-1, -1), (0, -1), (-1, 0), (0, 0)].iter().any(|&(ox, oy)| {
+            is(x + ox, y + oy) && is(x + ox + 1, y + oy) && is(x + ox, y + oy + 1) && is(x + ox + 1, y + oy + 1)
+        })
+    }
+
+    fn build(&mut self, tileset: &TileSet) -> TileData {
+
+        let options = self.options;
+        let (width, height) = (options.width, options.height);
+        let mut data = TileData::default();
+        data.width = width;
+        data.height = height;
+        data.tile_size = options.tile_size;
+        data.tilesets.push(MapTileset {
+            first_gid: 1,
+            source: options.tileset.clone()
+        });
+
+        let ground = &options.ground;
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = self.canonical(x, y);
+                let index = ((cx * 233 + cy * 107 + cx * cy) % ground.len().max(1) as u32) as usize;
+                data.indices.push(ground.get(index).cloned().unwrap_or(0));
+                data.flips.push(TileFlip::default());
+            }
+        }
+
+        // Fill in all terrains and let them reflow into valid groups
+        for (i, &(ref name, _)) in options.terrains.iter().enumerate() {
+            if let Some(terrain) = tileset.terrain(name) {
+
+                if !terrain.has_standalone_tiles() {
+                    self.remove_single_cells(i);
+                }
+
+                let cells: Vec<(i32, i32)> = (0..height as i32).flat_map(|y| {
+                    (0..width as i32).map(move |x| (x, y))
+
+                }).filter(|&(x, y)| self.get(x, y) == Some(Some(i))).collect();
+
+                for &(x, y) in &cells {
+                    data.set_tile_index(x, y, terrain.group_tile());
+                }
+
+                for &(x, y) in &cells {
+                    terrain.reflow(&mut data, x, y);
+                }
+
+            }
+        }
+
+        data
+
+    }
+
+}
+
+
+// Noise ----------------------------------------------------------------------
+
+/// Two octaves of smoothly interpolated value noise in the range of `0.0`
+/// to `1.0`.
+struct Noise {
+    octaves: Vec<(Vec<f32>, u32, u32, f32)>
+}
+
+impl Noise {
+
+    fn new(rng: &mut XorShiftRng, width: u32, height: u32) -> Self {
+        let octaves = [(12, 0.65), (5, 0.35)].iter().map(|&(scale, weight)| {
+            let (cols, rows) = (width / scale + 2, height / scale + 2);
+            let values = (0..cols * rows).map(|_| rng.gen::<f32>()).collect();
+            (values, cols, scale, weight)
+
+        }).collect();
+
+        Self {
+            octaves: octaves
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> f32 {
+        self.octaves.iter().map(|&(ref values, cols, scale, weight)| {
+            let (fx, fy) = (x as f32 / scale as f32, y as f32 / scale as f32);
+            let (ix, iy) = (fx as u32, fy as u32);
+            let (tx, ty) = (smooth(fx - ix as f32), smooth(fy - iy as f32));
+            let value = |x: u32, y: u32| values[(x + y * cols) as usize];
+            let top = value(ix, iy) + (value(ix + 1, iy) - value(ix, iy)) * tx;
+            let bottom = value(ix, iy + 1) + (value(ix + 1, iy + 1) - value(ix, iy + 1)) * tx;
+            (top + (bottom - top) * ty) * weight
+
+        }).sum()
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Checks whether all start locations are connected by traversable tiles.
+fn is_reachable(data: &TileData, tileset: &TileSet, starts: &[GridCell]) -> bool {
+
+    let mut nav = NavData::new(data.width, data.height);
+    nav.initialize(data, tileset);
+
+    let first = match starts.first() {
+        Some(start) => *start,
+        None => return true
+    };
+
+    let mut visited = vec![false; (data.width * data.height) as usize];
+    let mut open = VecDeque::new();
+    if nav.is_traversable(first.0, first.1) {
+        visited[(first.0 + first.1 * data.width as i32) as usize] = true;
+        open.push_back(first);
+    }
+
+    while let Some(cell) = open.pop_front() {
+        for (next, _) in nav.neighbors(cell.0, cell.1, |_, _| false) {
+            if nav.is_traversable(next.0, next.1) {
+                let i = (next.0 + next.1 * data.width as i32) as usize;
+                if !visited[i] {
+                    visited[i] = true;
+                    open.push_back(next);
+                }
+            }
+        }
+    }
+
+    starts.iter().all(|s| visited[(s.0 + s.1 * data.width as i32) as usize])
+
+}
+
@@ -9,25 +9,78 @@
 
 // STD Dependencies -----------------------------------------------------------
 use std::cmp;
+use std::mem;
 
 
 // External Dependencies ------------------------------------------------------
-use renderer::{ColorBuffer, Encoder, Factory, QuadView, Vertex};
+use renderer::{ColorBuffer, Encoder, Factory, QuadView, Texture, Vertex};
 
 
 // Internal Dependencies ------------------------------------------------------
-use ::data::TileData;
+use ::data::{TileData, TileFlip, TileLayer};
 use ::terrain::Terrain;
 use ::tileset::TileSet;
-use ::source::TileSource;
+use ::source::{TileChange, TileSource};
+
+
+// Statics --------------------------------------------------------------------
+const OVERLAY_PIXELS: [u8; 12] = [
+    0, 0, 0, 0,
+    0, 0, 0, 128,
+    0, 0, 0, 255
+];
+
+
+// Overlay Shades -------------------------------------------------------------
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OverlayShade {
+    Clear = 0,
+    Dim = 1,
+    Dark = 2
+}
+
+/// A visible cell showing an animated tile.
+#[derive(Debug, Copy, Clone)]
+struct AnimatedTile {
+    layer: Option<usize>,
+    x: u32,
+    y: u32,
+    tile: u32,
+    flip: TileFlip,
+    frame: u32
+}
+
+/// A decorative tile layer, empty tiles are collapsed into a single point.
+#[derive(Debug)]
+struct Layer {
+    quad_view: QuadView,
+    positions: Vec<[f32; 2]>,
+    tileset: usize
+}
+
+#[derive(Debug)]
+struct Overlay {
+    quad_view: QuadView,
+    shades: Vec<OverlayShade>,
+    dirty: bool
+}
 
 
 // Tilegrid Abstraction -------------------------------------------------------
 #[derive(Debug)]
 pub struct TileGrid<S> {
     tileset: TileSet,
+    /// Drawn instead of `tileset` while terrain lookups keep using it.
+    display_tileset: Option<TileSet>,
     source: S,
     quad_view: QuadView,
+    layers: Vec<Layer>,
+    layer_tilesets: Vec<TileSet>,
+    animated: Vec<AnimatedTile>,
+    time: u64,
+    overlay: Option<Overlay>,
+    view_width: u32,
+    view_height: u32,
     draw_size: u32,
     dirty: bool,
     border: u32,
@@ -56,45 +109,17 @@ impl<S> TileGrid<S> where S: TileSource {
     ) -> Self {
 
         let border = 4;
-        let ts = draw_size as f32;
         let (w, h) = (view_width / draw_size, view_height / draw_size);
         let (cols, rows) = (w + border * 2, h + border * 2);
 
-        let (bx, by) = (
-            -(view_width as f32 / 2.0) - ts * border as f32,
-            (view_height as f32 / 2.0) + (ts * border as f32 - ts).max(0.0)
+        let vertices = create_vertices(
+            view_width,
+            view_height,
+            draw_size,
+            border,
+            tileset.uvs(0)
         );
 
-        let mut vertices = Vec::with_capacity((cols * rows) as usize);
-        for y in 0..rows {
-            for x in 0..cols {
-
-                let uvs = tileset.uvs(0);
-                let (x, y) = (x as f32 * ts, y as f32 * ts);
-
-                let tr = ts;
-
-                // Top left
-                vertices.push(Vertex { pos: [bx + x, by - y], uv: uvs[0] });
-
-                // Top right
-                vertices.push(Vertex { pos: [bx + x + tr, by - y ], uv: uvs[1] });
-
-                // Bottom left
-                vertices.push(Vertex { pos: [bx + x, by - y + tr ], uv: uvs[2] });
-
-                // Top right
-                vertices.push(Vertex { pos: [bx + x + tr, by - y ], uv: uvs[1] });
-
-                // bottom right
-                vertices.push(Vertex { pos: [bx + x + tr, by - y + tr], uv: uvs[3] });
-
-                // Top bottom left
-                vertices.push(Vertex { pos: [bx + x, by - y + tr], uv: uvs[2] });
-
-            }
-        }
-
         let quad_view = QuadView::new(
             factory,
             color,
@@ -106,8 +131,16 @@ impl<S> TileGrid<S> where S: TileSource {
 
         Self {
             tileset: tileset,
+            display_tileset: None,
             source: S::default(),
             quad_view: quad_view,
+            layers: Vec::new(),
+            layer_tilesets: Vec::new(),
+            animated: Vec::new(),
+            time: 0,
+            overlay: None,
+            view_width: view_width,
+            view_height: view_height,
             draw_size: draw_size,
             dirty: true,
             border: border,
@@ -125,6 +158,109 @@ impl<S> TileGrid<S> where S: TileSource {
 
     }
 
+    /// Adds views for the additional tile layers of the source, which are
+    /// drawn in order between the base layer and the overlay.
+    ///
+    /// `tilesets` are all tilesets of the map after the first one, which is
+    /// the tileset of the grid itself.
+    pub fn with_layers(
+        mut self,
+        factory: &mut Factory,
+        color: ColorBuffer,
+        layers: &[TileLayer],
+        tilesets: Vec<TileSet>
+
+    ) -> Self {
+
+        self.layer_tilesets = tilesets;
+
+        for layer in layers {
+
+            let tileset = if layer.tileset > 0 && layer.tileset <= self.layer_tilesets.len() {
+                layer.tileset
+
+            } else {
+                0
+            };
+
+            let vertices = create_vertices(
+                self.view_width,
+                self.view_height,
+                self.draw_size,
+                self.border,
+                self.layer_tileset(tileset).uvs(0)
+            );
+
+            let positions = vertices.iter().map(|v| v.pos).collect();
+            let quad_view = QuadView::new(
+                factory,
+                color.clone(),
+                self.view_width,
+                self.view_height,
+                self.layer_tileset(tileset).texture().bind(),
+                vertices
+            );
+
+            self.layers.push(Layer {
+                quad_view: quad_view,
+                positions: positions,
+                tileset: tileset
+            });
+
+        }
+
+        self.dirty = true;
+        self
+
+    }
+
+    /// Adds a shading overlay which is drawn on top of the tiles, e.g. for
+    /// fog of war.
+    pub fn with_overlay(mut self, factory: &mut Factory, color: ColorBuffer) -> Self {
+
+        let texture = Texture::from_rgba(
+            factory,
+            (3, 1),
+            &OVERLAY_PIXELS[..]
+
+        ).expect("TileGrid: Failed to create overlay texture.");
+
+        let vertices = create_vertices(
+            self.view_width,
+            self.view_height,
+            self.draw_size,
+            self.border,
+            overlay_uvs(OverlayShade::Dark)
+        );
+
+        self.overlay = Some(Overlay {
+            quad_view: QuadView::new(
+                factory,
+                color,
+                self.view_width,
+                self.view_height,
+                texture.bind(),
+                vertices
+            ),
+            shades: Vec::new(),
+            dirty: true
+        });
+
+        self
+
+    }
+
+    /// Sets the overlay shade for every tile of the source, in row order.
+    ///
+    /// The overlay is rebuilt on every call, so callers should only pass in
+    /// shades which actually changed.
+    pub fn set_overlay(&mut self, shades: Vec<OverlayShade>) {
+        if let Some(ref mut overlay) = self.overlay {
+            overlay.shades = shades;
+            overlay.dirty = true;
+        }
+    }
+
     pub fn set_tile_index(&mut self, x: i32, y: i32, index: u32) {
         if self.source.set_tile_index(x, y, index) {
             self.dirty = true;
@@ -135,6 +271,13 @@ impl<S> TileGrid<S> where S: TileSource {
         self.source.get_tile_index(x, y)
     }
 
+    pub fn apply_diff(&mut self, changes: &[TileChange]) {
+        if !changes.is_empty() {
+            self.source.apply_diff(changes);
+            self.dirty = true;
+        }
+    }
+
     pub fn get_tile_terrain(&self, x: i32, y: i32) -> Option<&Terrain> {
         if let Some(index) = self.source.get_tile_index(x, y) {
             self.tileset.get_tile_terrain(index)
@@ -162,6 +305,32 @@ impl<S> TileGrid<S> where S: TileSource {
         &self.tileset
     }
 
+    /// Replaces a tileset without touching the source, `0` is the tileset
+    /// of the grid and all others are the tilesets of the layers.
+    pub fn set_tileset(&mut self, index: usize, tileset: TileSet) {
+
+        if index == 0 {
+            self.tileset = tileset;
+            self.display_tileset = None;
+
+        } else if index <= self.layer_tilesets.len() {
+            self.layer_tilesets[index - 1] = tileset;
+
+        } else {
+            return;
+        }
+
+        self.update_textures(index);
+
+    }
+
+    /// Replaces the tileset of the grid for drawing only, terrain lookups
+    /// keep using the tileset the grid was created with.
+    pub fn set_display_tileset(&mut self, tileset: TileSet) {
+        self.display_tileset = Some(tileset);
+        self.update_textures(0);
+    }
+
     pub fn source(&self) -> &S {
         &self.source
     }
@@ -194,29 +363,106 @@ impl<S> TileGrid<S> where S: TileSource {
         }
 
         // Scroll offset
-        self.quad_view.scroll_to(
+        let (sx, sy) = (
             -((scroll_x % (self.draw_size * self.border)) as f32),
             (scroll_y % (self.draw_size * self.border)) as f32
         );
 
+        self.quad_view.scroll_to(sx, sy);
+
+        for layer in &mut self.layers {
+            layer.quad_view.scroll_to(sx, sy);
+        }
+
+        if let Some(ref mut overlay) = self.overlay {
+            overlay.quad_view.scroll_to(sx, sy);
+        }
+
         (scroll_x as i32, scroll_y as i32)
 
     }
 
+    /// Advances animated tiles to the given render time in milliseconds.
+    ///
+    /// Only the cells whose animation frame changed are updated, all other
+    /// tiles are left untouched.
+    pub fn animate(&mut self, time: u64) {
+
+        self.time = time;
+
+        for a in 0..self.animated.len() {
+
+            let tile = self.animated[a];
+            let frame = self.animation_frame(tile.layer, tile.tile, time).unwrap_or(tile.tile);
+            if frame == tile.frame {
+                continue;
+            }
+
+            self.animated[a].frame = frame;
+
+            let index = ((tile.x + tile.y * self.cols) * 6) as usize;
+            let uvs = match tile.layer {
+                Some(l) => self.layer_tileset(self.layers[l].tileset).uvs(frame),
+                None => self.layer_tileset(0).uvs(frame)
+            };
+
+            let quad_view = match tile.layer {
+                Some(l) => &mut self.layers[l].quad_view,
+                None => &mut self.quad_view
+            };
+
+            let uvs = flip_uvs(uvs, tile.flip);
+            {
+                let vertices = quad_view.vertices_mut(index);
+                vertices[0].uv = uvs[0];
+                vertices[1].uv = uvs[1];
+                vertices[2].uv = uvs[2];
+                vertices[3].uv = uvs[1];
+                vertices[4].uv = uvs[3];
+                vertices[5].uv = uvs[2];
+            }
+            quad_view.set_dirty_range(index, index + 6);
+
+        }
+
+    }
+
     pub fn draw(&mut self, encoder: &mut Encoder) {
 
         if self.dirty {
             self.dirty = false;
             self.update_tiles();
             self.quad_view.set_dirty();
+
+            for layer in &mut self.layers {
+                layer.quad_view.set_dirty();
+            }
+
+            if let Some(ref mut overlay) = self.overlay {
+                overlay.dirty = true;
+            }
         }
 
         self.quad_view.draw(encoder, None);
 
+        for layer in &mut self.layers {
+            layer.quad_view.draw(encoder, None);
+        }
+
+        if self.overlay.as_ref().map(|o| o.dirty).unwrap_or(false) {
+            self.update_overlay();
+        }
+
+        if let Some(ref mut overlay) = self.overlay {
+            overlay.quad_view.draw(encoder, None);
+        }
+
     }
 
     fn update_tiles(&mut self) {
 
+        self.animated.clear();
+
         let ox = (self.gx * self.border) as isize - self.border as isize;
         let oy = (self.gy * self.border) as isize - self.border as isize;
 
@@ -229,16 +475,69 @@ impl<S> TileGrid<S> where S: TileSource {
                 let offset = (oy + y) * w + (ox + x as isize);
                 if offset >= 0 && offset < m {
                     let i = self.source.index(offset as usize);
-                    self.set_tile(x as u32, y as u32, i);
+                    let flip = self.source.flip(offset as usize);
+                    self.set_tile(x as u32, y as u32, i, flip);
+
+                    for l in 0..self.layers.len() {
+                        let (i, flip) = self.source.layers().get(l).map(|layer| (
+                            layer.indices.get(offset as usize).cloned().unwrap_or(None),
+                            layer.flips.get(offset as usize).cloned().unwrap_or_default()
+
+                        )).unwrap_or((None, TileFlip::default()));
+                        self.set_layer_tile(l, x as u32, y as u32, i, flip);
+                    }
                 }
 
             }
         }
     }
 
-    fn set_tile(&mut self, x: u32, y: u32, i: u32) {
+    fn update_overlay(&mut self) {
+
+        let ox = (self.gx * self.border) as isize - self.border as isize;
+        let oy = (self.gy * self.border) as isize - self.border as isize;
+
+        let (w, h) = (self.source.width() as isize, self.source.height() as isize);
+        let cols = self.cols;
+
+        if let Some(ref mut overlay) = self.overlay {
+
+            overlay.dirty = false;
+
+            for y in 0..self.rows as isize {
+                for x in 0..cols as isize {
+
+                    let (tx, ty) = (ox + x, oy + y);
+                    let shade = if tx >= 0 && tx < w && ty >= 0 && ty < h {
+                        overlay.shades.get((ty * w + tx) as usize).cloned().unwrap_or(OverlayShade::Clear)
+
+                    } else {
+                        OverlayShade::Dark
+                    };
+
+                    let index = ((x as u32 + y as u32 * cols) * 6) as usize;
+                    let uvs = overlay_uvs(shade);
+                    let vertices = overlay.quad_view.vertices_mut(index);
+                    vertices[0].uv = uvs[0];
+                    vertices[1].uv = uvs[1];
+                    vertices[2].uv = uvs[2];
+                    vertices[3].uv = uvs[1];
+                    vertices[4].uv = uvs[3];
+                    vertices[5].uv = uvs[2];
+
+                }
+            }
+
+            overlay.quad_view.set_dirty();
+
+        }
+
+    }
+
+    fn set_tile(&mut self, x: u32, y: u32, i: u32, flip: TileFlip) {
         let index = ((x + y * self.cols) * 6) as usize;
-        let uvs = self.tileset.uvs(i);
+        let frame = self.track_animation(None, x, y, i, flip);
+        let uvs = flip_uvs(self.layer_tileset(0).uvs(frame), flip);
         let vertices = self.quad_view.vertices_mut(index);
         vertices[0].uv = uvs[0];
         vertices[1].uv = uvs[1];
@@ -248,6 +547,73 @@ impl<S> TileGrid<S> where S: TileSource {
         vertices[5].uv = uvs[2];
     }
 
+    /// Remembers the cell in case its tile is animated and returns the tile
+    /// of the current animation frame.
+    fn track_animation(&mut self, layer: Option<usize>, x: u32, y: u32, tile: u32, flip: TileFlip) -> u32 {
+        let frame = self.animation_frame(layer, tile, self.time);
+        if let Some(frame) = frame {
+            self.animated.push(AnimatedTile {
+                layer: layer,
+                x: x,
+                y: y,
+                tile: tile,
+                flip: flip,
+                frame: frame
+            });
+        }
+        frame.unwrap_or(tile)
+    }
+
+    fn animation_frame(&self, layer: Option<usize>, tile: u32, time: u64) -> Option<u32> {
+        let tileset = match layer {
+            Some(l) => self.layer_tileset(self.layers[l].tileset),
+            None => self.layer_tileset(0)
+        };
+        tileset.animation(tile).map(|a| a.frame_at(time))
+    }
+
+    fn layer_tileset(&self, tileset: usize) -> &TileSet {
+        if tileset > 0 {
+            &self.layer_tilesets[tileset - 1]
+
+        } else {
+            self.display_tileset.as_ref().unwrap_or(&self.tileset)
+        }
+    }
+
+    fn update_textures(&mut self, tileset: usize) {
+
+        let texture = self.layer_tileset(tileset).texture().bind();
+        if tileset == 0 {
+            self.quad_view.set_texture(texture.clone());
+        }
+
+        for layer in &mut self.layers {
+            if layer.tileset == tileset {
+                layer.quad_view.set_texture(texture.clone());
+            }
+        }
+
+        self.dirty = true;
+
+    }
+
+    fn set_layer_tile(&mut self, l: usize, x: u32, y: u32, i: Option<u32>, flip: TileFlip) {
+        let index = ((x + y * self.cols) * 6) as usize;
+        let frame = i.map(|i| self.track_animation(Some(l), x, y, i, flip));
+        let uvs = flip_uvs(self.layer_tileset(self.layers[l].tileset).uvs(frame.unwrap_or(0)), flip);
+        let layer = &mut self.layers[l];
+        let vertices = layer.quad_view.vertices_mut(index);
+        for (v, uv) in vertices.iter_mut().zip([uvs[0], uvs[1], uvs[2], uvs[1], uvs[3], uvs[2]].iter()) {
+            v.uv = *uv;
+        }
+
+        // Empty tiles are hidden by collapsing their vertices
+        for (j, v) in vertices.iter_mut().enumerate() {
+            v.pos = layer.positions[index + if i.is_some() { j } else { 0 }];
+        }
+    }
+
     fn limit(&self, x: i32, y: i32) -> (u32, u32) {
         (
             cmp::min(cmp::max(x, 0) as u32, (cmp::max(self.source.width(), self.width) - self.width) * self.draw_size),
@@ -258,6 +624,104 @@ impl<S> TileGrid<S> where S: TileSource {
 }
 
 
+// Helpers --------------------------------------------------------------------
+
+/// Permutes the corner UVs of a tile according to its flip flags.
+///
+/// The UVs are ordered bottom left, bottom right, top left and top right.
+/// Each corner samples the texture at the position it ends up at after
+/// undoing the flips, i.e. vertical, horizontal and then diagonal.
+fn flip_uvs(uvs: [[f32; 2]; 4], flip: TileFlip) -> [[f32; 2]; 4] {
+
+    if !flip.is_flipped() {
+        return uvs;
+    }
+
+    let corners = [(0, 1), (1, 1), (0, 0), (1, 0)];
+    let mut flipped = uvs;
+    for (i, &(x, y)) in corners.iter().enumerate() {
+
+        let (mut x, mut y) = (x, y);
+        if flip.vertical {
+            y = 1 - y;
+        }
+
+        if flip.horizontal {
+            x = 1 - x;
+        }
+
+        if flip.diagonal {
+            mem::swap(&mut x, &mut y);
+        }
+
+        if let Some(j) = corners.iter().position(|&c| c == (x, y)) {
+            flipped[i] = uvs[j];
+        }
+
+    }
+
+    flipped
+
+}
+
+fn create_vertices(
+    view_width: u32,
+    view_height: u32,
+    draw_size: u32,
+    border: u32,
+    uvs: [[f32; 2]; 4]
+
+) -> Vec<Vertex> {
+
+    let ts = draw_size as f32;
+    let (w, h) = (view_width / draw_size, view_height / draw_size);
+    let (cols, rows) = (w + border * 2, h + border * 2);
+
+    let (bx, by) = (
+        -(view_width as f32 / 2.0) - ts * border as f32,
+        (view_height as f32 / 2.0) + (ts * border as f32 - ts).max(0.0)
+    );
+
+    let mut vertices = Vec::with_capacity((cols * rows) as usize);
+    for y in 0..rows {
+        for x in 0..cols {
+
+            let (x, y) = (x as f32 * ts, y as f32 * ts);
+
+            let tr = ts;
+
+            // Top left
+            vertices.push(Vertex { pos: [bx + x, by - y], uv: uvs[0], color: [1.0; 4] });
+
+            // Top right
+            vertices.push(Vertex { pos: [bx + x + tr, by - y ], uv: uvs[1], color: [1.0; 4] });
+
+            // Bottom left
+            vertices.push(Vertex { pos: [bx + x, by - y + tr ], uv: uvs[2], color: [1.0; 4] });
+
+            // Top right
+            vertices.push(Vertex { pos: [bx + x + tr, by - y ], uv: uvs[1], color: [1.0; 4] });
+
+            // bottom right
+            vertices.push(Vertex { pos: [bx + x + tr, by - y + tr], uv: uvs[3], color: [1.0; 4] });
+
+            // Top bottom left
+            vertices.push(Vertex { pos: [bx + x, by - y + tr], uv: uvs[2], color: [1.0; 4] });
+
+        }
+    }
+
+    vertices
+
+}
+
+fn overlay_uvs(shade: OverlayShade) -> [[f32; 2]; 4] {
+    // Sample the pixel center so neighbouring shades never bleed in
+    let u = (shade as u32 as f32 + 0.5) / 3.0;
+    [[u, 0.5], [u, 0.5], [u, 0.5], [u, 0.5]]
+}
+
+
 // Terrain Specific Grid Implementation ---------------------------------------
 pub type TerrainGrid = TileGrid<TileData>;
 
@@ -283,5 +747,51 @@ impl TerrainGrid {
         }
     }
 
+    /// Reflows the named terrain around the tile, after its tiles were set
+    /// directly.
+    pub fn reflow(&mut self, name: &str, x: i32, y: i32) {
+        if let Some(terrain) = self.tileset.terrain(name) {
+            terrain.reflow(&mut self.source, x, y);
+            self.dirty = true;
+        }
+    }
+
+    pub fn grow_tile(&mut self, name: &str, x: i32, y: i32) -> Option<&Terrain> {
+        self.set_terrain(name, x, y, true)
+    }
+
+    /// Grows or consumes the named terrain at the tile.
+    ///
+    /// Grown tiles become group or standalone tiles depending on their
+    /// neighbours, consumed ones become reduced tiles. Tiles which belong
+    /// to another terrain are left untouched, the terrain only grows onto
+    /// ground and its own reduced tiles.
+    pub fn set_terrain(&mut self, name: &str, x: i32, y: i32, grown: bool) -> Option<&Terrain> {
+        if let (Some(index), Some(terrain)) = (self.source.get_tile_index(x, y), self.tileset.terrain(name)) {
+
+            let other = self.tileset.get_tile_terrain(index).map(|t| t.name != terrain.name).unwrap_or(false);
+            let changed = if other {
+                false
+
+            } else if grown {
+                terrain.grow_tile(&self.tileset, &mut self.source, x, y)
+
+            } else {
+                terrain.consume_tile(&mut self.source, x, y)
+            };
+
+            if changed {
+                self.dirty = true;
+                Some(terrain)
+
+            } else {
+                None
+            }
+
+        } else {
+            None
+        }
+    }
+
 }
 
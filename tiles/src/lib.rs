@@ -8,7 +8,12 @@
 
 
 // Crates ---------------------------------------------------------------------
+extern crate rand;
 extern crate renderer;
+extern crate pathfinding;
+
+extern crate base64;
+extern crate flate2;
 
 extern crate serde;
 extern crate serde_xml_rs;
@@ -18,14 +23,22 @@ extern crate serde_derive;
 
 // Internal Dependencies ------------------------------------------------------
 mod data;
+mod generator;
 mod grid;
+mod nav;
+mod objects;
 mod source;
 mod terrain;
 mod tileset;
+mod writer;
 
-pub use self::data::TileData;
-pub use self::grid::{TileGrid, TerrainGrid};
+pub use self::data::{MapError, MapTileset, TileData, TileFlip, TileLayer};
+pub use self::generator::GeneratorOptions;
+pub use self::grid::{OverlayShade, TileGrid, TerrainGrid};
+pub use self::nav::{GridCell, NavData};
+pub use self::objects::{MapObject, MapObjects, MapProperty, ObjectDetails, ResourceSpawn, TriggerArea};
 pub use self::terrain::Terrain;
 pub use self::tileset::{TileSet, TileType};
-pub use self::source::TileSource;
+pub use self::source::{TileChange, TileSource};
+pub use self::writer::LayerEncoding;
 
@@ -0,0 +1,202 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::iter;
+
+
+// External Dependencies ------------------------------------------------------
+use pathfinding::astar;
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::data::TileData;
+use ::source::TileSource;
+use ::tileset::{TileSet, TileType};
+
+
+// Statics --------------------------------------------------------------------
+const NAV_GROUND: u32 = 0;
+const NAV_OTHER: u32 = 2;
+const NAV_WATER: u32 = 4;
+const NAV_BLOCKED_COST: usize = 4096;
+
+
+// Grid Cell ------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GridCell(pub i32, pub i32);
+
+
+// Navigation Data ------------------------------------------------------------
+
+/// Traversability information for a tile map.
+///
+/// The indices map directly onto the `debug.tsx` tileset so the data can be
+/// rendered through a `TileGrid` for debugging purposes.
+pub struct NavData {
+    pub width: u32,
+    pub height: u32,
+    pub indices: Vec<u32>
+}
+
+impl NavData {
+
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width,
+            height: height,
+            indices: iter::repeat(NAV_OTHER).take((width * height) as usize).collect()
+        }
+    }
+
+    pub fn initialize(&mut self, data: &TileData, tileset: &TileSet) {
+        for (i, d) in self.indices.iter_mut().zip(data.indices().iter()) {
+            *i = NavData::index_from_type(tileset.typ(*d));
+        }
+    }
+
+    pub fn update_tile(&mut self, data: &TileData, tileset: &TileSet, x: i32, y: i32) {
+        if let Some(index) = data.get_tile_index(x, y) {
+            self.set_tile_index(x, y, NavData::index_from_type(tileset.typ(index)));
+        }
+    }
+
+    /// Marks a tile as impassable, e.g. when it is covered by a structure.
+    pub fn block_tile(&mut self, x: i32, y: i32) {
+        self.set_tile_index(x, y, NAV_OTHER);
+    }
+
+    pub fn neighbors<F>(&self, x: i32, y: i32, blocked: F) -> Vec<(GridCell, usize)> where F: Fn(i32, i32) -> bool {
+        let mut cells = Vec::new();
+        for px in x - 1..x + 2 {
+            for py in y - 1..y + 2 {
+                if px != x || py != y {
+                    if self.is_traversable(px, py) && !blocked(px, py) {
+                        cells.push((GridCell(px, py), self.distance(x, y, px, py)));
+
+                    } else {
+                        cells.push((GridCell(px, py), NAV_BLOCKED_COST));
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn distance(&self, x: i32, y: i32, tx: i32, ty: i32) -> usize {
+        if (tx == x && ty != y) || (ty == y && tx != x) {
+            1
+
+        } else {
+            2
+        }
+    }
+
+    pub fn is_traversable(&self, x: i32, y: i32) -> bool {
+        if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
+            let i = x + y * self.width as i32;
+            self.indices[i as usize] == NAV_GROUND
+
+        } else {
+            false
+        }
+    }
+
+    /// Searches a path from `begin` towards `goal`.
+    ///
+    /// Cells for which `blocked` returns `true` are treated like impassable
+    /// terrain. In case the goal itself cannot be reached the path ends in
+    /// front of the first impassable cell along the way.
+    ///
+    /// The returned path is reversed, so the next cell to visit is always the
+    /// last element.
+    pub fn find_path<F>(&self, begin: GridCell, goal: GridCell, blocked: F) -> Option<Vec<GridCell>> where F: Fn(i32, i32) -> bool {
+
+        let result = astar(&begin, |c| {
+            self.neighbors(c.0, c.1, &blocked)
+
+        }, |c| {
+            ((c.0 - goal.0).abs() + (c.1 - goal.1).abs()) as usize
+
+        }, |c| {
+            *c == goal
+        });
+
+        result.map(|result| {
+            let mut path: Vec<GridCell> = result.0.into_iter().skip(1).take_while(|c| {
+                self.is_traversable(c.0, c.1) && !blocked(c.0, c.1)
+
+            }).collect();
+            path.reverse();
+            path
+        })
+
+    }
+
+    fn index_from_type(typ: TileType) -> u32 {
+        match typ {
+            TileType::Ground => NAV_GROUND,
+            TileType::Water => NAV_WATER,
+            TileType::Other => NAV_OTHER
+        }
+    }
+
+}
+
+impl TileSource for NavData {
+
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn index(&self, index: usize) -> u32 {
+        self.indices[index]
+    }
+
+    fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    fn set_tile_index(&mut self, x: i32, y: i32, index: u32) -> bool {
+        let i = y * self.width as i32 + x;
+        if i >= 0 && i < (self.width * self.height) as i32 {
+            self.indices[i as usize] = index;
+            true
+
+        } else {
+            false
+        }
+    }
+
+    fn get_tile_index(&self, x: i32, y: i32) -> Option<u32> {
+        let w = self.width as i32;
+        let h = self.height as i32;
+        if x >= 0 && x < w && y >= 0 && y < h {
+            Some(self.indices[(y * w + x) as usize])
+
+        } else {
+            None
+        }
+    }
+
+}
+
+impl Default for NavData {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            indices: Vec::new()
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::collections::HashMap;
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::nav::GridCell;
+
+
+// Map Objects ----------------------------------------------------------------
+
+/// Typed objects from all object groups of a map.
+///
+/// Objects are classified by their Tiled type, all positions of typed
+/// objects are converted from pixels into map tiles.
+///
+/// Names, additional properties and objects of unknown types are not used by
+/// the game, they are only kept so saving a map does not lose them.
+#[derive(Debug, Clone, Default)]
+pub struct MapObjects {
+    /// Player start locations, ordered by object ID.
+    pub starts: Vec<GridCell>,
+    /// Names and properties of start locations, by their cell.
+    pub start_details: HashMap<GridCell, ObjectDetails>,
+    pub resources: Vec<ResourceSpawn>,
+    pub triggers: Vec<TriggerArea>,
+    pub other: Vec<MapObject>
+}
+
+/// A custom property of an object, `typ` is the Tiled type of its value.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MapProperty {
+    pub name: String,
+    pub typ: Option<String>,
+    pub value: String
+}
+
+/// Name and additional properties of a start location.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ObjectDetails {
+    pub name: String,
+    pub properties: Vec<MapProperty>
+}
+
+/// A tile with a custom resource amount, objects of type `resource`.
+///
+/// The `resource` property names the kind of resource and `amount` overrides
+/// the default amount of the tile.
+#[derive(Debug, Clone)]
+pub struct ResourceSpawn {
+    pub cell: GridCell,
+    pub resource: String,
+    pub amount: Option<u16>,
+    pub name: String,
+    pub properties: Vec<MapProperty>
+}
+
+/// A named rectangular area of the map, objects of type `trigger`.
+#[derive(Debug, Clone)]
+pub struct TriggerArea {
+    pub name: String,
+    pub origin: GridCell,
+    pub size: (u32, u32),
+    pub properties: Vec<MapProperty>
+}
+
+impl TriggerArea {
+    pub fn contains(&self, cell: GridCell) -> bool {
+        cell.0 >= self.origin.0 && cell.0 < self.origin.0 + self.size.0 as i32
+            && cell.1 >= self.origin.1 && cell.1 < self.origin.1 + self.size.1 as i32
+    }
+}
+
+/// An object of an unknown type, its position and size are kept in pixels.
+#[derive(Debug, Clone)]
+pub struct MapObject {
+    pub name: String,
+    pub typ: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub properties: Vec<MapProperty>
+}
@@ -7,6 +7,23 @@
 // except according to those terms.
 
 
+// Internal Dependencies ------------------------------------------------------
+use ::data::{TileFlip, TileLayer};
+
+
+// Tile Changes ---------------------------------------------------------------
+
+/// A single tile which differs between two sources, either in its index or
+/// in its flip flags.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TileChange {
+    pub x: i32,
+    pub y: i32,
+    pub index: u32,
+    pub flip: TileFlip
+}
+
+
 // Traits ---------------------------------------------------------------------
 pub trait TileSource: Default {
     fn set_tile_index(&mut self, x: i32, y: i32, index: u32) -> bool where Self: Sized;
@@ -15,5 +32,49 @@ pub trait TileSource: Default {
     fn height(&self) -> u32 where Self: Sized;
     fn index(&self, index: usize) -> u32 where Self: Sized;
     fn indices(&self) -> &[u32] where Self: Sized;
+
+    /// Additional layers which are drawn on top of the indices.
+    fn layers(&self) -> &[TileLayer] where Self: Sized {
+        &[]
+    }
+
+    /// Flip flags of the tile at the given index.
+    fn flip(&self, _: usize) -> TileFlip where Self: Sized {
+        TileFlip::default()
+    }
+
+    /// Sets the flip flags of the tile at the given position, sources
+    /// without flips ignore them.
+    fn set_tile_flip(&mut self, _: i32, _: i32, _: TileFlip) -> bool where Self: Sized {
+        false
+    }
+
+    /// Returns all tiles which differ from `original`, e.g. to bring a
+    /// freshly loaded map up to date with a running game.
+    ///
+    /// Both sources are expected to have the same size.
+    fn diff(&self, original: &Self) -> Vec<TileChange> where Self: Sized {
+        let width = self.width().max(1) as usize;
+        self.indices().iter().zip(original.indices().iter()).enumerate().filter(|&(i, (a, b))| {
+            a != b || self.flip(i) != original.flip(i)
+
+        }).map(|(i, (index, _))| {
+            TileChange {
+                x: (i % width) as i32,
+                y: (i / width) as i32,
+                index: *index,
+                flip: self.flip(i)
+            }
+
+        }).collect()
+    }
+
+    fn apply_diff(&mut self, changes: &[TileChange]) where Self: Sized {
+        // Setting the index resets the flips, so they are applied last
+        for change in changes {
+            self.set_tile_index(change.x, change.y, change.index);
+            self.set_tile_flip(change.x, change.y, change.flip);
+        }
+    }
 }
 
@@ -10,10 +10,11 @@
 // Internal Dependencies ------------------------------------------------------
 use ::data::TileData;
 use ::source::TileSource;
+use ::tileset::{TileSet, TileType};
 
 
 // Terrain Abstraction --------------------------------------------------------
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Terrain {
     pub name: String,
     group: [u32; 13],
@@ -48,66 +49,126 @@ impl Terrain {
 
                 let tree = self.reduced_tile(x as u32, y as u32);
                 data.set_tile_index(x, y, tree);
+                self.reflow(data, x, y);
+                true
 
-                // 1. Go outwards and convert all tiles which do not form a
-                //    2x2 group into standalone tiles
-                let mut distance = 1;
-                loop {
+            } else {
+                false
+            }
 
-                    let mut standalone = 0;
-                    for offset in data.get_offset_border(x, y, distance) {
+        } else {
+            false
+        }
 
-                        if let Some(o) = offset {
+    }
 
-                            // Check if surrounding tile is part of terrain too
-                            let tile = data.get_tile_index(o.0, o.1).unwrap();
-                            if self.is_group_tile(tile) {
+    /// The inverse of `consume_tile`, turns the tile into part of this
+    /// terrain and reflows the surrounding tiles.
+    ///
+    /// Standalone neighbours are merged into groups where possible. Only
+    /// terrains with standalone tiles can grow, since single tiles fall back
+    /// to them, and only onto ground or tiles of the terrain itself.
+    pub fn grow_tile(&self, tileset: &TileSet, data: &mut TileData, x: i32, y: i32) -> bool {
 
-                                // Get surrounding tiles
-                                let flags = self.to_flags(data.get_surrounding_indices(o.0, o.1));
+        if self.standalone.is_empty() {
+            false
 
-                                // Convert into standalone
-                                if let Some(tile) = self.flags_to_standalone(o.0 as u32, o.1 as u32, flags) {
-                                    data.set_tile_index(o.0, o.1, tile);
-                                    standalone += 1;
-                                }
+        } else if let Some(tile) = data.get_tile_index(x, y) {
+            if self.is_group_tile(tile) || !(self.has_tile(tile) || tileset.typ(tile) == TileType::Ground) {
+                false
 
-                            }
-                        }
+            } else {
 
+                data.set_tile_index(x, y, self.group[4]);
+
+                // Standalone neighbours may now form groups with the tile
+                for offset in data.get_offset_border(x, y, 1) {
+                    if let Some(o) = offset {
+                        if self.is_standalone_tile(data.get_tile_index(o.0, o.1).unwrap()) {
+                            data.set_tile_index(o.0, o.1, self.group[4]);
+                        }
                     }
+                }
 
-                    if standalone == 0 {
-                        break;
+                self.reflow(data, x, y);
+                true
 
-                    } else {
-                        distance += 1;
-                    }
+            }
 
-                }
+        } else {
+            false
+        }
 
-                // 2. We now reflow all bordering tiles within the adjusted area
-                for offset in data.get_offset_area(x, y, distance + 1) {
-                    if let Some(o) = offset {
-                        let tile = data.get_tile_index(o.0, o.1).unwrap();
-                        if self.is_group_tile(tile) {
-                            let indices = data.get_surrounding_indices(o.0, o.1);
-                            let tile = self.flags_to_grouped(self.to_flags(indices));
+    }
+
+    /// Converts all group tiles around the changed tile at `x` and `y` into
+    /// standalone tiles or correctly bordered group tiles.
+    pub fn reflow(&self, data: &mut TileData, x: i32, y: i32) {
+
+        // 1. Go outwards, starting with the tile itself, and convert all
+        //    tiles which do not form a 2x2 group into standalone tiles
+        let mut distance = 0;
+        loop {
+
+            let mut standalone = 0;
+            for offset in data.get_offset_border(x, y, distance) {
+
+                if let Some(o) = offset {
+
+                    // Check if surrounding tile is part of terrain too
+                    let tile = data.get_tile_index(o.0, o.1).unwrap();
+                    if self.is_group_tile(tile) {
+
+                        // Get surrounding tiles
+                        let flags = self.to_flags(data.get_surrounding_indices(o.0, o.1));
+
+                        // Convert into standalone
+                        if let Some(tile) = self.flags_to_standalone(o.0 as u32, o.1 as u32, flags) {
                             data.set_tile_index(o.0, o.1, tile);
+                            standalone += 1;
                         }
+
                     }
                 }
 
-                true
+            }
+
+            if standalone == 0 && distance > 0 {
+                break;
 
             } else {
-                false
+                distance += 1;
             }
 
-        } else {
-            false
         }
 
+        // 2. We now reflow all bordering tiles within the adjusted area
+        for offset in data.get_offset_area(x, y, distance + 1) {
+            if let Some(o) = offset {
+                let tile = data.get_tile_index(o.0, o.1).unwrap();
+                if self.is_group_tile(tile) {
+                    let indices = data.get_surrounding_indices(o.0, o.1);
+                    let tile = self.flags_to_grouped(self.to_flags(indices));
+                    data.set_tile_index(o.0, o.1, tile);
+                }
+            }
+        }
+
+    }
+
+    /// The center tile of the group, which is fully surrounded by the
+    /// terrain.
+    pub fn group_tile(&self) -> u32 {
+        self.group[4]
+    }
+
+    pub fn has_standalone_tiles(&self) -> bool {
+        !self.standalone.is_empty()
+    }
+
+    /// Returns whether the tile is a not yet consumed part of the terrain.
+    pub fn is_consumable_tile(&self, tile: u32) -> bool {
+        !self.reduced.is_empty() && (self.is_group_tile(tile) || self.is_standalone_tile(tile))
     }
 
     pub fn has_tile(&self, tile: u32) -> bool {
@@ -26,7 +26,7 @@ use ::terrain::Terrain;
 
 
 // Tiletypes ------------------------------------------------------------------
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum TileType {
     Ground,
     Water,
@@ -34,14 +34,41 @@ pub enum TileType {
 }
 
 
+// Tile Animations ------------------------------------------------------------
+
+/// Frames of an animated tile, each shown for its duration in milliseconds.
+#[derive(Debug, Clone)]
+pub struct TileAnimation {
+    frames: Vec<(u32, u32)>,
+    duration: u32
+}
+
+impl TileAnimation {
+
+    /// Returns the tile which is shown at the given render time.
+    pub fn frame_at(&self, time: u64) -> u32 {
+        let mut t = (time % self.duration as u64) as u32;
+        for &(tile, duration) in &self.frames {
+            if t < duration {
+                return tile;
+            }
+            t -= duration;
+        }
+        self.frames[0].0
+    }
+
+}
+
+
 // Tileset Abstraction --------------------------------------------------------
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TileSet {
     cols: u32,
     rows: u32,
     texture: Texture,
     terrains: HashMap<String, Terrain>,
-    tile_types: Vec<TileType>
+    tile_types: Vec<TileType>,
+    animations: HashMap<u32, TileAnimation>
 }
 
 impl TileSet {
@@ -65,6 +92,30 @@ impl TileSet {
 
         let mut types: Vec<TileType> = iter::repeat(TileType::Other).take((cols * rows) as usize).collect();
 
+        // Animations without any frames or without a duration are ignored
+        let mut animations = HashMap::new();
+        for t in &set.tile {
+            if let (Ok(tile_index), Some(animation)) = (t.id.parse::<u32>(), t.animation.as_ref()) {
+
+                let frames: Vec<(u32, u32)> = animation.frame.iter().filter_map(|f| {
+                    match (f.tileid.parse::<u32>(), f.duration.parse::<u32>()) {
+                        (Ok(tile), Ok(duration)) => Some((tile, duration)),
+                        _ => None
+                    }
+
+                }).collect();
+
+                let duration = frames.iter().map(|&(_, d)| d).sum();
+                if duration > 0 {
+                    animations.insert(tile_index, TileAnimation {
+                        frames: frames,
+                        duration: duration
+                    });
+                }
+
+            }
+        }
+
         // Extract terrains from tiles
         let mut tiles: Vec<(u32, u32, u32)> = set.tile.into_iter().filter_map(|t| {
             if let Ok(tile_index) = t.id.parse::<u32>() {
@@ -168,11 +219,20 @@ impl TileSet {
             rows: size.1 / rows,
             texture: texture,
             terrains: terrains,
-            tile_types: types
+            tile_types: types,
+            animations: animations
         })
 
     }
 
+    /// Returns the image source of a tileset file without loading the
+    /// texture, relative to the tileset file.
+    pub fn image_source(path: &Path) -> Result<String, Box<Error>> {
+        let file = File::open(path)?;
+        let set: Set = deserialize(file)?;
+        Ok(set.image.source)
+    }
+
     pub fn typ(&self, tile: u32) -> TileType {
         if tile < self.tile_types.len() as u32 {
             self.tile_types[tile as usize]
@@ -182,6 +242,10 @@ impl TileSet {
         }
     }
 
+    pub fn animation(&self, tile: u32) -> Option<&TileAnimation> {
+        self.animations.get(&tile)
+    }
+
     pub fn terrain(&self, name: &str) -> Option<&Terrain> {
         self.terrains.get(name)
     }
@@ -199,6 +263,17 @@ impl TileSet {
         &self.texture
     }
 
+    /// Number of tile columns and rows in the texture.
+    pub fn size(&self) -> (u32, u32) {
+        (self.cols, self.rows)
+    }
+
+    pub fn terrain_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.terrains.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        names
+    }
+
     pub fn uvs(&self, index: u32) -> [[f32; 2]; 4] {
         let (w, h) = (1.0 / self.cols as f32, 1.0 / self.rows as f32);
         let (x, y) = (
@@ -271,6 +346,19 @@ struct Tile {
     id: String,
     #[serde(rename(deserialize="type"))]
     typ: Option<String>,
-    terrain: Option<String>
+    terrain: Option<String>,
+    animation: Option<Animation>
+}
+
+#[derive(Debug, Deserialize)]
+struct Animation {
+    #[serde(default)]
+    frame: Vec<Frame>
+}
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    tileid: String,
+    duration: String
 }
 
@@ -0,0 +1,511 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::io;
+use std::fs::File;
+use std::path::Path;
+use std::io::{Read, Write};
+
+
+// External Dependencies ------------------------------------------------------
+use base64;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+
+// Internal Dependencies ------------------------------------------------------
+use ::data::{MapError, MapTileset, TileData, TileFlip, TileLayer};
+use ::nav::GridCell;
+use ::objects::{MapProperty, ResourceSpawn, TriggerArea};
+
+
+// Statics --------------------------------------------------------------------
+const BINARY_MAGIC: &'static [u8; 4] = b"RTSM";
+const BINARY_VERSION: u8 = 1;
+const MAX_BINARY_SIZE: u64 = 16 * 1024 * 1024;
+
+
+// Layer Encodings ------------------------------------------------------------
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LayerEncoding {
+    Csv,
+    Base64,
+    Base64Zlib
+}
+
+
+// Map Writer -----------------------------------------------------------------
+impl TileData {
+
+    /// Saves the map as a Tiled `.tmx` file.
+    pub fn save(&self, path: &Path, encoding: LayerEncoding) -> Result<(), MapError> {
+        let mut file = File::create(path)?;
+        self.write_tmx(&mut file, encoding)
+    }
+
+    /// Writes all tile layers and objects in Tiled's `.tmx` format.
+    ///
+    /// Tilesets are written as references to their original `.tsx` files.
+    pub fn write_tmx<W: Write>(&self, out: &mut W, encoding: LayerEncoding) -> Result<(), MapError> {
+
+        let objects = self.objects.starts.len() + self.objects.resources.len()
+                    + self.objects.triggers.len() + self.objects.other.len();
+
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            out,
+            r#"<map version="1.0.0" orientation="orthogonal" renderorder="right-down" width="{}" height="{}" tilewidth="{}" tileheight="{}" nextobjectid="{}">"#,
+            self.width, self.height, self.tile_size.0, self.tile_size.1, objects + 1
+        )?;
+
+        for tileset in &self.tilesets {
+            writeln!(out, r#" <tileset firstgid="{}" source="{}"/>"#, tileset.first_gid, escape(&tileset.source))?;
+        }
+
+        // Base layer
+        let first_gid = self.first_gid(0);
+        let gids: Vec<u32> = self.indices.iter().enumerate().map(|(i, index)| {
+            let flip = self.flips.get(i).cloned().unwrap_or_default();
+            (first_gid + index) | flip.to_bits()
+
+        }).collect();
+        write_layer(out, &self.base_name, self.width, self.height, &gids, encoding)?;
+
+        // Layers which were split up by tileset are merged back into one
+        let mut l = 0;
+        while l < self.layers.len() {
+
+            let mut gids = self.layer_gids(&self.layers[l]);
+            let mut next = l + 1;
+            while next < self.layers.len() && self.layers[next].name == self.layers[l].name {
+
+                let other = self.layer_gids(&self.layers[next]);
+                if gids.iter().zip(other.iter()).any(|(a, b)| *a != 0 && *b != 0) {
+                    break;
+                }
+
+                for (gid, other) in gids.iter_mut().zip(other.into_iter()) {
+                    if other != 0 {
+                        *gid = other;
+                    }
+                }
+                next += 1;
+
+            }
+
+            write_layer(out, &self.layers[l].name, self.width, self.height, &gids, encoding)?;
+            l = next;
+
+        }
+
+        if objects > 0 {
+            self.write_objects(out)?;
+        }
+
+        writeln!(out, "</map>")?;
+        Ok(())
+
+    }
+
+    /// Serializes the map into a compact binary format, all tile data is
+    /// compressed with zlib.
+    ///
+    /// Only the data the game needs is included, layer and object names,
+    /// additional properties and unknown objects are left out.
+    pub fn to_binary(&self) -> Vec<u8> {
+
+        let mut body = Vec::new();
+        write_u32(&mut body, self.width);
+        write_u32(&mut body, self.height);
+        write_u32(&mut body, self.tile_size.0);
+        write_u32(&mut body, self.tile_size.1);
+
+        write_u32(&mut body, self.tilesets.len() as u32);
+        for tileset in &self.tilesets {
+            write_u32(&mut body, tileset.first_gid);
+            write_string(&mut body, &tileset.source);
+        }
+
+        for (i, index) in self.indices.iter().enumerate() {
+            let flip = self.flips.get(i).cloned().unwrap_or_default();
+            write_u32(&mut body, index | flip.to_bits());
+        }
+
+        // Layer indices are offset by one so zero can mark empty tiles
+        write_u32(&mut body, self.layers.len() as u32);
+        for layer in &self.layers {
+            write_string(&mut body, &layer.name);
+            write_u32(&mut body, layer.tileset as u32);
+            for (i, index) in layer.indices.iter().enumerate() {
+                let flip = layer.flips.get(i).cloned().unwrap_or_default();
+                write_u32(&mut body, index.map(|i| (i + 1) | flip.to_bits()).unwrap_or(0));
+            }
+        }
+
+        write_u32(&mut body, self.objects.starts.len() as u32);
+        for start in &self.objects.starts {
+            write_cell(&mut body, *start);
+        }
+
+        write_u32(&mut body, self.objects.resources.len() as u32);
+        for spawn in &self.objects.resources {
+            write_cell(&mut body, spawn.cell);
+            write_string(&mut body, &spawn.resource);
+            write_u32(&mut body, spawn.amount.map(|a| a as u32 + 1).unwrap_or(0));
+        }
+
+        write_u32(&mut body, self.objects.triggers.len() as u32);
+        for trigger in &self.objects.triggers {
+            write_string(&mut body, &trigger.name);
+            write_cell(&mut body, trigger.origin);
+            write_u32(&mut body, trigger.size.0);
+            write_u32(&mut body, trigger.size.1);
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.push(BINARY_VERSION);
+
+        // Writing into memory cannot fail
+        let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+        encoder.write_all(&body).expect("TileData: Failed to compress map.");
+        encoder.finish().expect("TileData: Failed to compress map.")
+
+    }
+
+    /// Deserializes a map which was serialized via `to_binary`.
+    ///
+    /// The data may come from other peers, so all sizes are validated
+    /// before anything is allocated and the decompressed data is limited to
+    /// 16 MiB.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, MapError> {
+
+        if bytes.len() < 5 || &bytes[0..4] != BINARY_MAGIC || bytes[4] != BINARY_VERSION {
+            return Err(invalid_binary());
+        }
+
+        let mut body = Vec::new();
+        ZlibDecoder::new(&bytes[5..]).take(MAX_BINARY_SIZE + 1).read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_BINARY_SIZE {
+            return Err(invalid_binary());
+        }
+
+        let mut reader = BinaryReader {
+            bytes: &body,
+            offset: 0
+        };
+
+        let mut data = TileData::default();
+        data.width = reader.u32()?;
+        data.height = reader.u32()?;
+        data.tile_size = (reader.u32()?, reader.u32()?);
+
+        for _ in 0..reader.u32()? {
+            data.tilesets.push(MapTileset {
+                first_gid: reader.u32()?,
+                source: reader.string()?
+            });
+        }
+
+        // Every tile takes up four bytes, so larger maps cannot be valid
+        let count = data.width.checked_mul(data.height).map(|c| c as usize).ok_or_else(invalid_binary)?;
+        if count > body.len() / 4 {
+            return Err(invalid_binary());
+        }
+
+        for _ in 0..count {
+            let (index, flip) = TileFlip::from_gid(reader.u32()?);
+            data.indices.push(index);
+            data.flips.push(flip);
+        }
+
+        for _ in 0..reader.u32()? {
+            let mut layer = TileLayer {
+                name: reader.string()?,
+                tileset: reader.u32()? as usize,
+                indices: Vec::with_capacity(count),
+                flips: Vec::with_capacity(count)
+            };
+            if layer.tileset >= data.tilesets.len() {
+                return Err(invalid_binary());
+            }
+            for _ in 0..count {
+                let (index, flip) = TileFlip::from_gid(reader.u32()?);
+                layer.indices.push(index.checked_sub(1));
+                layer.flips.push(flip);
+            }
+            data.layers.push(layer);
+        }
+
+        for _ in 0..reader.u32()? {
+            let cell = reader.cell()?;
+            data.objects.starts.push(cell);
+        }
+
+        for _ in 0..reader.u32()? {
+            data.objects.resources.push(ResourceSpawn {
+                cell: reader.cell()?,
+                resource: reader.string()?,
+                amount: reader.u32()?.checked_sub(1).map(|a| a as u16),
+                name: String::new(),
+                properties: Vec::new()
+            });
+        }
+
+        for _ in 0..reader.u32()? {
+            data.objects.triggers.push(TriggerArea {
+                name: reader.string()?,
+                origin: reader.cell()?,
+                size: (reader.u32()?, reader.u32()?),
+                properties: Vec::new()
+            });
+        }
+
+        Ok(data)
+
+    }
+
+    fn first_gid(&self, tileset: usize) -> u32 {
+        self.tilesets.get(tileset).map(|t| t.first_gid).unwrap_or(1)
+    }
+
+    fn layer_gids(&self, layer: &TileLayer) -> Vec<u32> {
+        let first_gid = self.first_gid(layer.tileset);
+        layer.indices.iter().enumerate().map(|(i, index)| {
+            let flip = layer.flips.get(i).cloned().unwrap_or_default();
+            index.map(|index| (first_gid + index) | flip.to_bits()).unwrap_or(0)
+
+        }).collect()
+    }
+
+    fn write_objects<W: Write>(&self, out: &mut W) -> Result<(), MapError> {
+
+        let (tw, th) = self.tile_size;
+        let mut id = 1;
+
+        writeln!(out, r#" <objectgroup name="Objects">"#)?;
+
+        for start in &self.objects.starts {
+            let (name, properties) = self.objects.start_details.get(start).map(|d| {
+                (d.name.as_str(), &d.properties[..])
+
+            }).unwrap_or(("", &[][..]));
+            write!(out, r#"  <object id="{}""#, id)?;
+            write_name(out, name)?;
+            write!(out, r#" type="start" x="{}" y="{}""#, start.0 * tw as i32, start.1 * th as i32)?;
+            write_properties(out, properties, &[])?;
+            id += 1;
+        }
+
+        for spawn in &self.objects.resources {
+            let mut known = vec![("resource", spawn.resource.clone())];
+            if let Some(amount) = spawn.amount {
+                known.push(("amount", amount.to_string()));
+            }
+            write!(out, r#"  <object id="{}""#, id)?;
+            write_name(out, &spawn.name)?;
+            write!(out, r#" type="resource" x="{}" y="{}""#, spawn.cell.0 * tw as i32, spawn.cell.1 * th as i32)?;
+            write_properties(out, &spawn.properties, &known)?;
+            id += 1;
+        }
+
+        for trigger in &self.objects.triggers {
+            write!(out, r#"  <object id="{}""#, id)?;
+            write_name(out, &trigger.name)?;
+            write!(
+                out,
+                r#" type="trigger" x="{}" y="{}" width="{}" height="{}""#,
+                trigger.origin.0 * tw as i32, trigger.origin.1 * th as i32,
+                trigger.size.0 * tw, trigger.size.1 * th
+            )?;
+            write_properties(out, &trigger.properties, &[])?;
+            id += 1;
+        }
+
+        for object in &self.objects.other {
+            write!(out, r#"  <object id="{}""#, id)?;
+            write_name(out, &object.name)?;
+            if let Some(ref typ) = object.typ {
+                write!(out, r#" type="{}""#, escape(typ))?;
+            }
+            write!(out, r#" x="{}" y="{}""#, object.x, object.y)?;
+            if let Some(width) = object.width {
+                write!(out, r#" width="{}""#, width)?;
+            }
+            if let Some(height) = object.height {
+                write!(out, r#" height="{}""#, height)?;
+            }
+            write_properties(out, &object.properties, &[])?;
+            id += 1;
+        }
+
+        writeln!(out, " </objectgroup>")?;
+        Ok(())
+
+    }
+
+}
+
+
+// Helpers --------------------------------------------------------------------
+fn write_layer<W: Write>(
+    out: &mut W,
+    name: &str,
+    width: u32,
+    height: u32,
+    gids: &[u32],
+    encoding: LayerEncoding
+
+) -> Result<(), MapError> {
+
+    writeln!(out, r#" <layer name="{}" width="{}" height="{}">"#, escape(name), width, height)?;
+
+    match encoding {
+        LayerEncoding::Csv => {
+            writeln!(out, r#"  <data encoding="csv">"#)?;
+            let rows: Vec<String> = gids.chunks(width.max(1) as usize).map(|row| {
+                row.iter().map(|g| g.to_string()).collect::<Vec<String>>().join(",")
+
+            }).collect();
+            writeln!(out, "{}", rows.join(",\n"))?;
+        },
+        LayerEncoding::Base64 | LayerEncoding::Base64Zlib => {
+
+            let mut bytes = Vec::with_capacity(gids.len() * 4);
+            for gid in gids {
+                write_u32(&mut bytes, *gid);
+            }
+
+            if encoding == LayerEncoding::Base64Zlib {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&bytes)?;
+                bytes = encoder.finish()?;
+                writeln!(out, r#"  <data encoding="base64" compression="zlib">"#)?;
+
+            } else {
+                writeln!(out, r#"  <data encoding="base64">"#)?;
+            }
+
+            writeln!(out, "   {}", base64::encode(&bytes))?;
+
+        }
+    }
+
+    writeln!(out, "  </data>")?;
+    writeln!(out, " </layer>")?;
+    Ok(())
+
+}
+
+fn write_name<W: Write>(out: &mut W, name: &str) -> Result<(), MapError> {
+    if !name.is_empty() {
+        write!(out, r#" name="{}""#, escape(name))?;
+    }
+    Ok(())
+}
+
+/// Closes an object tag, the `known` properties are written before all
+/// additional ones.
+fn write_properties<W: Write>(out: &mut W, properties: &[MapProperty], known: &[(&str, String)]) -> Result<(), MapError> {
+
+    if properties.is_empty() && known.is_empty() {
+        writeln!(out, "/>")?;
+        return Ok(());
+    }
+
+    writeln!(out, ">")?;
+    writeln!(out, "   <properties>")?;
+
+    for &(name, ref value) in known {
+        writeln!(out, r#"    <property name="{}" value="{}"/>"#, name, escape(value))?;
+    }
+
+    for property in properties {
+        if let Some(ref typ) = property.typ {
+            writeln!(
+                out,
+                r#"    <property name="{}" type="{}" value="{}"/>"#,
+                escape(&property.name), escape(typ), escape(&property.value)
+            )?;
+
+        } else {
+            writeln!(out, r#"    <property name="{}" value="{}"/>"#, escape(&property.name), escape(&property.value))?;
+        }
+    }
+
+    writeln!(out, "   </properties>")?;
+    writeln!(out, "  </object>")?;
+    Ok(())
+
+}
+
+fn invalid_binary() -> MapError {
+    MapError::InvalidData("binary".to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.push(value as u8);
+    out.push((value >> 8) as u8);
+    out.push((value >> 16) as u8);
+    out.push((value >> 24) as u8);
+}
+
+fn write_cell(out: &mut Vec<u8>, cell: GridCell) {
+    write_u32(out, cell.0 as u32);
+    write_u32(out, cell.1 as u32);
+}
+
+fn write_string(out: &mut Vec<u8>, text: &str) {
+    write_u32(out, text.len() as u32);
+    out.extend_from_slice(text.as_bytes());
+}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    offset: usize
+}
+
+impl<'a> BinaryReader<'a> {
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], MapError> {
+        if count <= self.bytes.len() - self.offset {
+            let bytes = &self.bytes[self.offset..self.offset + count];
+            self.offset += count;
+            Ok(bytes)
+
+        } else {
+            Err(MapError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated map data")))
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, MapError> {
+        let b = self.take(4)?;
+        Ok(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+    }
+
+    fn cell(&mut self) -> Result<GridCell, MapError> {
+        Ok(GridCell(self.u32()? as i32, self.u32()? as i32))
+    }
+
+    fn string(&mut self) -> Result<String, MapError> {
+        let length = self.u32()? as usize;
+        let bytes = self.take(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid_binary())
+    }
+
+}
@@ -0,0 +1,260 @@
+// Copyright (c) 2017 Ivo Wetzel
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+// Crates ---------------------------------------------------------------------
+extern crate flate2;
+extern crate tiles;
+
+
+// STD Dependencies -----------------------------------------------------------
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+
+// External Dependencies ------------------------------------------------------
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use tiles::{GridCell, LayerEncoding, MapError, TileData, TileSource};
+
+
+// Helpers --------------------------------------------------------------------
+
+/// Writes a 2x2 map with the given tilesets and layers into a temporary
+/// file, every test uses its own file name.
+fn write_map(name: &str, tilesets: &str, layers: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("tiles-test-{}.tmx", name));
+    let mut file = File::create(&path).unwrap();
+    write!(
+        file,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.0" orientation="orthogonal" width="2" height="2" tilewidth="32" tileheight="32">
+{}
+{}
+</map>"#,
+        tilesets,
+        layers
+
+    ).unwrap();
+    path
+}
+
+fn csv_layer(name: &str, gids: &str) -> String {
+    format!(r#"<layer name="{}" width="2" height="2"><data encoding="csv">{}</data></layer>"#, name, gids)
+}
+
+const TWO_TILESETS: &'static str = r#"<tileset firstgid="1" source="a.tsx"/><tileset firstgid="101" source="b.tsx"/>"#;
+
+
+// Tilesets -------------------------------------------------------------------
+#[test]
+fn test_decoration_layer_with_mixed_tilesets_is_split() {
+
+    let layers = format!("{}{}", csv_layer("Ground", "1,2,3,4"), csv_layer("Details", "0,101,5,0"));
+    let data = TileData::new(&write_map("mixed-decoration", TWO_TILESETS, &layers)).unwrap();
+
+    assert_eq!(data.layers.len(), 2);
+    assert_eq!(data.layers[0].name, "Details");
+    assert_eq!(data.layers[0].tileset, 0);
+    assert_eq!(data.layers[0].indices, vec![None, None, Some(4), None]);
+    assert_eq!(data.layers[1].name, "Details");
+    assert_eq!(data.layers[1].tileset, 1);
+    assert_eq!(data.layers[1].indices, vec![None, Some(0), None, None]);
+
+}
+
+#[test]
+fn test_split_layers_are_merged_when_written() {
+
+    let layers = format!("{}{}", csv_layer("Ground", "1,2,3,4"), csv_layer("Details", "0,101,5,0"));
+    let data = TileData::new(&write_map("mixed-write", TWO_TILESETS, &layers)).unwrap();
+
+    let mut tmx = Vec::new();
+    data.write_tmx(&mut tmx, LayerEncoding::Csv).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    assert_eq!(tmx.matches("<layer ").count(), 2);
+
+    let path = env::temp_dir().join("tiles-test-mixed-write-again.tmx");
+    File::create(&path).unwrap().write_all(tmx.as_bytes()).unwrap();
+
+    let reloaded = TileData::new(&path).unwrap();
+    assert_eq!(reloaded.layers.len(), 2);
+    assert_eq!(reloaded.layers[0].indices, data.layers[0].indices);
+    assert_eq!(reloaded.layers[1].indices, data.layers[1].indices);
+
+}
+
+#[test]
+fn test_base_layer_must_use_first_tileset() {
+    let layers = csv_layer("Ground", "1,101,1,1");
+    match TileData::new(&write_map("mixed-base", TWO_TILESETS, &layers)) {
+        Err(MapError::MixedTilesets(ref name)) => assert_eq!(name, "Ground"),
+        other => panic!("expected MixedTilesets, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_embedded_tilesets_are_rejected() {
+    let tilesets = r#"<tileset firstgid="1" name="embedded" tilewidth="32" tileheight="32"/>"#;
+    match TileData::new(&write_map("embedded", tilesets, &csv_layer("Ground", "1,1,1,1"))) {
+        Err(MapError::EmbeddedTileset(gid)) => assert_eq!(gid, 1),
+        other => panic!("expected EmbeddedTileset, got {:?}", other)
+    }
+}
+
+
+// Round Trips ----------------------------------------------------------------
+fn develop_map(name: &str) -> TileData {
+    let layers = format!("{}{}", csv_layer("Ground", "1,2,2147483651,4"), csv_layer("Details", "0,101,0,0"));
+    let mut data = TileData::new(&write_map(name, TWO_TILESETS, &layers)).unwrap();
+    data.objects.starts.push(GridCell(1, 0));
+    data
+}
+
+fn assert_same_map(a: &TileData, b: &TileData) {
+    assert_eq!((a.width, a.height, a.tile_size), (b.width, b.height, b.tile_size));
+    assert_eq!(a.indices, b.indices);
+    assert_eq!(a.flips, b.flips);
+    assert_eq!(a.layers.len(), b.layers.len());
+    for (la, lb) in a.layers.iter().zip(b.layers.iter()) {
+        assert_eq!((&la.name, la.tileset, &la.indices, &la.flips), (&lb.name, lb.tileset, &lb.indices, &lb.flips));
+    }
+    assert_eq!(a.objects.starts, b.objects.starts);
+}
+
+fn tmx_round_trip(name: &str, encoding: LayerEncoding) {
+
+    let data = develop_map(name);
+    assert!(data.flips[2].horizontal);
+
+    let path = env::temp_dir().join(format!("tiles-test-{}-saved.tmx", name));
+    data.save(&path, encoding).unwrap();
+    assert_same_map(&data, &TileData::new(&path).unwrap());
+
+}
+
+#[test]
+fn test_tmx_round_trip_csv() {
+    tmx_round_trip("round-trip-csv", LayerEncoding::Csv);
+}
+
+#[test]
+fn test_tmx_round_trip_base64() {
+    tmx_round_trip("round-trip-base64", LayerEncoding::Base64);
+}
+
+#[test]
+fn test_tmx_round_trip_base64_zlib() {
+    tmx_round_trip("round-trip-base64-zlib", LayerEncoding::Base64Zlib);
+}
+
+#[test]
+fn test_binary_round_trip() {
+    let data = develop_map("round-trip-binary");
+    assert_same_map(&data, &TileData::from_binary(&data.to_binary()).unwrap());
+}
+
+#[test]
+fn test_diff_round_trip() {
+
+    let original = develop_map("round-trip-diff");
+    let mut changed = original.clone();
+    changed.set_tile_index(0, 0, 7);
+    changed.set_tile_index(1, 1, 8);
+
+    let changes = changed.diff(&original);
+    assert_eq!(changes.len(), 2);
+
+    let mut patched = original.clone();
+    patched.apply_diff(&changes);
+    assert_same_map(&changed, &patched);
+    assert!(patched.diff(&changed).is_empty());
+
+}
+
+#[test]
+fn test_diff_restores_flips() {
+
+    let original = develop_map("diff-flips");
+    let mut changed = original.clone();
+    changed.set_tile_index(0, 1, 9);
+    assert!(!changed.flips[2].is_flipped());
+
+    let mut undone = changed.clone();
+    undone.apply_diff(&original.diff(&changed));
+    assert!(undone.flips[2].horizontal);
+    assert_same_map(&original, &undone);
+
+}
+
+#[test]
+fn test_names_and_unknown_objects_are_saved() {
+
+    let objects = r#"<layer name="Base" width="2" height="2"><data encoding="csv">1,1,1,1</data></layer>
+<objectgroup name="Objects">
+ <object id="1" name="North" type="start" x="32" y="0"><properties><property name="team" value="1"/></properties></object>
+ <object id="2" name="Mine" type="resource" x="0" y="32"><properties><property name="resource" value="gold"/><property name="hint" type="int" value="3"/></properties></object>
+ <object id="3" name="Spawn" type="waypoint" x="16.5" y="8" width="4" height="4"/>
+</objectgroup>"#;
+
+    let data = TileData::new(&write_map("objects", TWO_TILESETS, objects)).unwrap();
+    let path = env::temp_dir().join("tiles-test-objects-saved.tmx");
+    data.save(&path, LayerEncoding::Csv).unwrap();
+
+    let saved = TileData::new(&path).unwrap();
+    assert_eq!(saved.base_name, "Base");
+    assert_eq!(saved.objects.start_details[&GridCell(1, 0)].name, "North");
+    assert_eq!(saved.objects.start_details[&GridCell(1, 0)].properties[0].value, "1");
+    assert_eq!(saved.objects.resources[0].name, "Mine");
+    assert_eq!(saved.objects.resources[0].resource, "gold");
+    assert_eq!(saved.objects.resources[0].properties.len(), 1);
+    assert_eq!(saved.objects.resources[0].properties[0].typ, Some("int".to_string()));
+    assert_eq!(saved.objects.other.len(), 1);
+    assert_eq!(saved.objects.other[0].typ, Some("waypoint".to_string()));
+    assert_eq!((saved.objects.other[0].x, saved.objects.other[0].width), (16.5, Some(4.0)));
+
+}
+
+
+// Untrusted Binary Data ------------------------------------------------------
+fn binary(body: &[u32]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    for value in body {
+        raw.extend_from_slice(&[*value as u8, (*value >> 8) as u8, (*value >> 16) as u8, (*value >> 24) as u8]);
+    }
+    let mut encoder = ZlibEncoder::new(b"RTSM\x01".to_vec(), Compression::default());
+    encoder.write_all(&raw).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_binary_with_overflowing_size_is_rejected() {
+    match TileData::from_binary(&binary(&[0xFFFF_FFFF, 0xFFFF_FFFF, 32, 32, 0])) {
+        Err(MapError::InvalidData(_)) => {},
+        other => panic!("expected InvalidData, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_binary_larger_than_its_data_is_rejected() {
+    match TileData::from_binary(&binary(&[65536, 65536, 32, 32, 0])) {
+        Err(MapError::InvalidData(_)) => {},
+        other => panic!("expected InvalidData, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_truncated_binary_is_rejected() {
+    let data = develop_map("truncated-binary");
+    let mut bytes = data.to_binary();
+    let length = bytes.len();
+    bytes.truncate(length / 2);
+    assert!(TileData::from_binary(&bytes).is_err());
+}